@@ -0,0 +1,358 @@
+//! Optional balance ledger tied to the order book.
+//!
+//! When enabled with
+//! [`OrderBookBuilder::with_accounts`](crate::OrderBookBuilder::with_accounts),
+//! every order must belong to an owner with enough free balance:
+//! - a limit buy reserves `price × quantity` of the quote asset
+//! - a limit sell reserves `quantity` of the base asset
+//! - a market order is settled directly from the free balance
+//!
+//! Each fill settles base and quote between maker and taker, and canceling an
+//! order releases whatever is still reserved.
+//!
+//! Fees are paid in the quote asset. When a [`FeeSchedule`](crate::FeeSchedule)
+//! is configured, buy orders also reserve the fee at the highest rate of the
+//! schedule, and the unused part is released on settlement.
+
+use std::{collections::HashMap, ops::Sub};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{make_error, ErrorType, Result},
+    fees::fee,
+    order::{OwnerId, Price, Quantity},
+    risk::notional,
+    Side,
+};
+
+/// The two assets traded by the order book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Asset {
+    /// The asset being bought or sold (e.g. `BTC` in `BTCUSD`), counted in quantity units.
+    Base,
+    /// The asset used to pay (e.g. `USD` in `BTCUSD`), counted in `price × quantity` units.
+    Quote,
+}
+
+/// Balance of a single asset.
+///
+/// # Fields
+/// - `free`: Amount available for new orders or withdrawals
+/// - `reserved`: Amount locked by resting orders
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Balance {
+    pub free: u128,
+    pub reserved: u128,
+}
+
+impl Balance {
+    /// Total amount, free and reserved.
+    pub fn total(&self) -> u128 {
+        self.free.saturating_add(self.reserved)
+    }
+}
+
+/// Base and quote balances of an owner.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Account {
+    pub base: Balance,
+    pub quote: Balance,
+}
+
+impl Account {
+    fn balance_mut(&mut self, asset: Asset) -> &mut Balance {
+        match asset {
+            Asset::Base => &mut self.base,
+            Asset::Quote => &mut self.quote,
+        }
+    }
+}
+
+/// Adds a signed amount to a balance, saturating at zero and `u128::MAX`.
+fn add_signed(value: u128, delta: i128) -> u128 {
+    if delta >= 0 {
+        value.saturating_add(delta as u128)
+    } else {
+        value.saturating_sub(delta.unsigned_abs())
+    }
+}
+
+/// The ledger holding the balances of every owner.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Accounts {
+    pub(crate) accounts: HashMap<OwnerId, Account>,
+    /// Rate reserved on top of the notional of buy orders to cover their fees
+    pub(crate) fee_buffer_bps: u64,
+}
+
+impl Accounts {
+    pub(crate) fn new(fee_buffer_bps: u64) -> Self {
+        Self { accounts: HashMap::new(), fee_buffer_bps }
+    }
+
+    /// The asset and amount an order of the given side locks.
+    pub(crate) fn reservation(
+        &self,
+        side: Side,
+        price: Price,
+        quantity: Quantity,
+    ) -> (Asset, u128) {
+        match side {
+            Side::Buy => {
+                let value = notional(price, quantity);
+                let buffer = fee(value, self.fee_buffer_bps as i64) as u128;
+                (Asset::Quote, value.saturating_add(buffer))
+            }
+            Side::Sell => (Asset::Base, u128::from(quantity.value())),
+        }
+    }
+
+    pub(crate) fn get(&self, owner: OwnerId) -> Option<Account> {
+        self.accounts.get(&owner).copied()
+    }
+
+    pub(crate) fn deposit(&mut self, owner: OwnerId, asset: Asset, amount: u128) {
+        let balance = self.accounts.entry(owner).or_default().balance_mut(asset);
+        balance.free = balance.free.saturating_add(amount);
+    }
+
+    pub(crate) fn withdraw(&mut self, owner: OwnerId, asset: Asset, amount: u128) -> Result<()> {
+        self.debit_free(owner, asset, amount)
+    }
+
+    /// Checks that the owner can pay `amount` of `asset` from the free balance.
+    pub(crate) fn ensure_free(&self, owner: OwnerId, asset: Asset, amount: u128) -> Result<()> {
+        let free = self.get(owner).map(|a| match asset {
+            Asset::Base => a.base.free,
+            Asset::Quote => a.quote.free,
+        });
+        if free.unwrap_or(0) < amount {
+            return Err(make_error(ErrorType::InsufficientBalance));
+        }
+        Ok(())
+    }
+
+    /// Checks that the owner can replace the reservation of an order at
+    /// `old` price and quantity with one at `new`, counting the funds the
+    /// order frees.
+    pub(crate) fn ensure_replaceable(
+        &self,
+        owner: OwnerId,
+        side: Side,
+        old: (Price, Quantity),
+        new: (Price, Quantity),
+    ) -> Result<()> {
+        let (asset, freed) = self.reservation(side, old.0, old.1);
+        let (_, needed) = self.reservation(side, new.0, new.1);
+        self.ensure_free(owner, asset, needed.saturating_sub(freed))
+    }
+
+    /// Moves the funds needed by a limit order from free to reserved.
+    pub(crate) fn reserve(
+        &mut self,
+        owner: OwnerId,
+        side: Side,
+        price: Price,
+        quantity: Quantity,
+    ) -> Result<()> {
+        let (asset, amount) = self.reservation(side, price, quantity);
+        self.ensure_free(owner, asset, amount)?;
+        let balance = self.accounts.entry(owner).or_default().balance_mut(asset);
+        balance.free -= amount;
+        balance.reserved += amount;
+        Ok(())
+    }
+
+    /// Moves the funds still locked by a limit order back to free.
+    pub(crate) fn release(&mut self, owner: OwnerId, side: Side, price: Price, quantity: Quantity) {
+        let (asset, amount) = self.reservation(side, price, quantity);
+        let balance = self.accounts.entry(owner).or_default().balance_mut(asset);
+        let amount = amount.min(balance.reserved);
+        balance.reserved -= amount;
+        balance.free = balance.free.saturating_add(amount);
+    }
+
+    /// Exchanges base and quote for a fill of `quantity` at `price`, and charges `fee`
+    /// in quote.
+    ///
+    /// `reserved` is the price the order reserved its funds at together with the
+    /// quantity it had left before this fill, or `None` for a market order which
+    /// pays directly from the free balance. The reservation is consumed as the
+    /// difference between what the remaining quantity locks before and after the
+    /// fill, so that the reserved funds of an order always add up exactly, and any
+    /// price improvement or unused fee buffer goes back to the free balance.
+    pub(crate) fn settle(
+        &mut self,
+        owner: OwnerId,
+        side: Side,
+        price: Price,
+        quantity: Quantity,
+        reserved: Option<(Price, Quantity)>,
+        fee: i128,
+    ) {
+        let value = notional(price, quantity) as i128;
+        let reserved = reserved.map(|(reserved_price, remaining)| {
+            self.reservation(side, reserved_price, remaining).1
+                - self.reservation(side, reserved_price, remaining.sub(quantity)).1
+        });
+        let account = self.accounts.entry(owner).or_default();
+        let qty = u128::from(quantity.value());
+        match side {
+            Side::Buy => {
+                let mut delta = -value - fee;
+                if let Some(reserved) = reserved {
+                    account.quote.reserved = account.quote.reserved.saturating_sub(reserved);
+                    delta += reserved as i128;
+                }
+                account.quote.free = add_signed(account.quote.free, delta);
+                account.base.free = account.base.free.saturating_add(qty);
+            }
+            Side::Sell => {
+                if reserved.is_some() {
+                    account.base.reserved = account.base.reserved.saturating_sub(qty);
+                } else {
+                    account.base.free = account.base.free.saturating_sub(qty);
+                }
+                account.quote.free = add_signed(account.quote.free, value - fee);
+            }
+        }
+    }
+
+    fn debit_free(&mut self, owner: OwnerId, asset: Asset, amount: u128) -> Result<()> {
+        self.ensure_free(owner, asset, amount)?;
+        let balance = self.accounts.entry(owner).or_default().balance_mut(asset);
+        balance.free -= amount;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: OwnerId = OwnerId(1);
+
+    #[test]
+    fn test_deposit_and_withdraw() {
+        let mut accounts = Accounts::default();
+        accounts.deposit(ALICE, Asset::Base, 10);
+        accounts.deposit(ALICE, Asset::Quote, 1000);
+        assert_eq!(accounts.get(ALICE).unwrap().base.free, 10);
+        assert_eq!(accounts.get(ALICE).unwrap().quote.free, 1000);
+
+        assert!(accounts.withdraw(ALICE, Asset::Base, 4).is_ok());
+        assert_eq!(accounts.get(ALICE).unwrap().base.free, 6);
+        assert!(accounts
+            .withdraw(ALICE, Asset::Base, 7)
+            .is_err_and(|e| e.code == make_error(ErrorType::InsufficientBalance).code));
+        assert!(accounts.withdraw(OwnerId(2), Asset::Quote, 1).is_err());
+    }
+
+    #[test]
+    fn test_reserve_and_release() {
+        let mut accounts = Accounts::default();
+        accounts.deposit(ALICE, Asset::Quote, 1000);
+
+        assert!(accounts.reserve(ALICE, Side::Buy, Price(100), Quantity(11)).is_err());
+        assert!(accounts.reserve(ALICE, Side::Buy, Price(100), Quantity(6)).is_ok());
+        let quote = accounts.get(ALICE).unwrap().quote;
+        assert_eq!((quote.free, quote.reserved), (400, 600));
+        assert_eq!(quote.total(), 1000);
+
+        accounts.release(ALICE, Side::Buy, Price(100), Quantity(2));
+        let quote = accounts.get(ALICE).unwrap().quote;
+        assert_eq!((quote.free, quote.reserved), (600, 400));
+    }
+
+    #[test]
+    fn test_settlement() {
+        let bob = OwnerId(2);
+        let mut accounts = Accounts::default();
+        accounts.deposit(ALICE, Asset::Base, 10);
+        accounts.deposit(bob, Asset::Quote, 2000);
+
+        // Alice rests a sell at 100, Bob buys with a limit at 120
+        accounts.reserve(ALICE, Side::Sell, Price(100), Quantity(10)).unwrap();
+        accounts.reserve(bob, Side::Buy, Price(120), Quantity(10)).unwrap();
+        accounts.settle(
+            ALICE,
+            Side::Sell,
+            Price(100),
+            Quantity(10),
+            Some((Price(100), Quantity(10))),
+            0,
+        );
+        accounts.settle(
+            bob,
+            Side::Buy,
+            Price(100),
+            Quantity(10),
+            Some((Price(120), Quantity(10))),
+            0,
+        );
+
+        let alice = accounts.get(ALICE).unwrap();
+        assert_eq!(alice.base, Balance { free: 0, reserved: 0 });
+        assert_eq!(alice.quote, Balance { free: 1000, reserved: 0 });
+        let bob_account = accounts.get(bob).unwrap();
+        assert_eq!(bob_account.base, Balance { free: 10, reserved: 0 });
+        assert_eq!(bob_account.quote, Balance { free: 1000, reserved: 0 });
+
+        // market taker pays from free balance
+        accounts.reserve(ALICE, Side::Buy, Price(100), Quantity(5)).unwrap();
+        accounts.settle(
+            ALICE,
+            Side::Buy,
+            Price(100),
+            Quantity(5),
+            Some((Price(100), Quantity(5))),
+            0,
+        );
+        accounts.settle(bob, Side::Sell, Price(100), Quantity(5), None, 0);
+        assert_eq!(accounts.get(ALICE).unwrap().base.free, 5);
+        assert_eq!(accounts.get(bob).unwrap().base.free, 5);
+        assert_eq!(accounts.get(bob).unwrap().quote.free, 1500);
+    }
+
+    #[test]
+    fn test_settlement_with_fees() {
+        let bob = OwnerId(2);
+        // fees up to 10 bps are reserved on buy orders
+        let mut accounts = Accounts::new(10);
+        accounts.deposit(ALICE, Asset::Quote, 10_010);
+        accounts.deposit(bob, Asset::Base, 10);
+
+        assert!(accounts.reserve(ALICE, Side::Buy, Price(1000), Quantity(11)).is_err());
+        accounts.reserve(ALICE, Side::Buy, Price(1000), Quantity(10)).unwrap();
+        assert_eq!(accounts.get(ALICE).unwrap().quote, Balance { free: 0, reserved: 10_010 });
+
+        // Alice is the maker with a 1 bps rebate, Bob the taker at 5 bps
+        let reserved = Some((Price(1000), Quantity(10)));
+        accounts.settle(ALICE, Side::Buy, Price(1000), Quantity(10), reserved, -1);
+        accounts.settle(bob, Side::Sell, Price(1000), Quantity(10), None, 5);
+
+        // the unused fee buffer and the rebate go back to the free balance
+        assert_eq!(accounts.get(ALICE).unwrap().quote, Balance { free: 11, reserved: 0 });
+        assert_eq!(accounts.get(ALICE).unwrap().base.free, 10);
+        assert_eq!(accounts.get(bob).unwrap().quote.free, 9_995);
+        assert_eq!(accounts.get(bob).unwrap().base, Balance { free: 0, reserved: 0 });
+    }
+
+    #[test]
+    fn test_partial_fills_consume_exact_reservation() {
+        let mut accounts = Accounts::new(10);
+        accounts.deposit(ALICE, Asset::Quote, 4);
+        // 3 @ 1 reserves 3 + a fee buffer rounded up to 1
+        accounts.reserve(ALICE, Side::Buy, Price(1), Quantity(3)).unwrap();
+        assert_eq!(accounts.get(ALICE).unwrap().quote, Balance { free: 0, reserved: 4 });
+
+        for remaining in [3, 2, 1] {
+            let reserved = Some((Price(1), Quantity(remaining)));
+            accounts.settle(ALICE, Side::Buy, Price(1), Quantity(1), reserved, 0);
+        }
+        assert_eq!(accounts.get(ALICE).unwrap().quote, Balance { free: 1, reserved: 0 });
+        assert_eq!(accounts.get(ALICE).unwrap().base.free, 3);
+    }
+}
@@ -0,0 +1,252 @@
+//! Call auction price discovery.
+//!
+//! During an auction phase ([`TradingPhase::PreOpen`](crate::TradingPhase::PreOpen)
+//! or [`TradingPhase::Auction`](crate::TradingPhase::Auction)) orders are
+//! collected without matching and the book may be crossed.
+//! [`OrderBook::uncross`](crate::OrderBook::uncross) executes every crossing
+//! order at the single equilibrium price chosen by the following rules:
+//!
+//! 1. the price that maximizes the executed volume;
+//! 2. among those, the price with the minimum imbalance (surplus);
+//! 3. if the surplus is on the buy side at every remaining price, the highest
+//!    price, if it is on the sell side at every remaining price, the lowest;
+//! 4. otherwise the price closest to the reference price, the lowest one on a tie
+//!    or when no reference price is available.
+//!
+//! Market orders taking part in the auction (market-on-open and market-on-close)
+//! are willing to trade at any price. When there are no limit prices at all, they
+//! are executed at the reference price.
+
+use std::{
+    cmp::Ordering,
+    ops::{Add, Sub},
+};
+
+use crate::{
+    order::{Price, Quantity},
+    Side,
+};
+
+/// Indicative result of an auction, if the book was uncrossed now.
+///
+/// # Fields
+/// - `price`: The indicative uncross price
+/// - `matched_qty`: The quantity that would be executed at `price`
+/// - `surplus_qty`: The quantity that would be left unexecuted at `price`
+/// - `surplus_side`: The side of the surplus, `None` if the auction is balanced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndicativeAuction {
+    pub price: Price,
+    pub matched_qty: Quantity,
+    pub surplus_qty: Quantity,
+    pub surplus_side: Option<Side>,
+}
+
+/// The equilibrium of a crossed book.
+///
+/// # Fields
+/// - `price`: The auction price
+/// - `volume`: The quantity executed at `price`
+/// - `buy_qty`: Total bid quantity willing to buy at `price`
+/// - `sell_qty`: Total ask quantity willing to sell at `price`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Equilibrium {
+    pub(crate) price: Price,
+    pub(crate) volume: Quantity,
+    pub(crate) buy_qty: Quantity,
+    pub(crate) sell_qty: Quantity,
+}
+
+impl Equilibrium {
+    /// Signed surplus: positive on the buy side, negative on the sell side.
+    pub(crate) fn surplus(&self) -> i128 {
+        i128::from(self.buy_qty.value()) - i128::from(self.sell_qty.value())
+    }
+
+    pub(crate) fn indicative(&self) -> IndicativeAuction {
+        let (surplus_qty, surplus_side) = match self.buy_qty.cmp(&self.sell_qty) {
+            Ordering::Greater => (self.buy_qty.sub(self.sell_qty), Some(Side::Buy)),
+            Ordering::Less => (self.sell_qty.sub(self.buy_qty), Some(Side::Sell)),
+            Ordering::Equal => (Quantity(0), None),
+        };
+        IndicativeAuction { price: self.price, matched_qty: self.volume, surplus_qty, surplus_side }
+    }
+}
+
+/// Computes the equilibrium of a book from its aggregated levels and the
+/// quantity of the market orders on each side.
+///
+/// `bids` must be sorted by descending price and `asks` by ascending price.
+/// Returns `None` if the book is not crossed.
+pub(crate) fn equilibrium(
+    bids: &[(Price, Quantity)],
+    asks: &[(Price, Quantity)],
+    market_buy: Quantity,
+    market_sell: Quantity,
+    reference: Option<Price>,
+) -> Option<Equilibrium> {
+    let cumulative = |levels: &[(Price, Quantity)]| {
+        levels
+            .iter()
+            .scan(Quantity(0), |total, (_, qty)| {
+                *total = total.add(*qty);
+                Some(*total)
+            })
+            .collect::<Vec<_>>()
+    };
+    let bid_totals = cumulative(bids);
+    let ask_totals = cumulative(asks);
+
+    let mut prices: Vec<Price> = bids.iter().chain(asks.iter()).map(|(price, _)| *price).collect();
+    if prices.is_empty() {
+        prices.extend(reference);
+    }
+    let mut candidates: Vec<Equilibrium> = prices
+        .iter()
+        .map(|price| {
+            let bid_levels = bids.partition_point(|(p, _)| p >= price);
+            let ask_levels = asks.partition_point(|(p, _)| p <= price);
+            let buy_qty = bid_levels.checked_sub(1).map_or(Quantity(0), |i| bid_totals[i]);
+            let sell_qty = ask_levels.checked_sub(1).map_or(Quantity(0), |i| ask_totals[i]);
+            let buy_qty = buy_qty.add(market_buy);
+            let sell_qty = sell_qty.add(market_sell);
+            Equilibrium { price: *price, volume: buy_qty.min(sell_qty), buy_qty, sell_qty }
+        })
+        .collect();
+    candidates.sort_by_key(|c| c.price);
+    candidates.dedup_by_key(|c| c.price);
+
+    // 1. maximum executed volume
+    let volume = candidates.iter().map(|c| c.volume).max()?;
+    if volume.value() == 0 {
+        return None;
+    }
+    candidates.retain(|c| c.volume == volume);
+
+    // 2. minimum imbalance
+    let imbalance = candidates.iter().map(|c| c.surplus().abs()).min()?;
+    candidates.retain(|c| c.surplus().abs() == imbalance);
+
+    // 3. market pressure
+    if candidates.iter().all(|c| c.surplus() > 0) {
+        return candidates.last().copied();
+    }
+    if candidates.iter().all(|c| c.surplus() < 0) {
+        return candidates.first().copied();
+    }
+
+    // 4. closeness to the reference price
+    match reference {
+        Some(reference) => {
+            candidates.iter().min_by_key(|c| c.price.value().abs_diff(reference.value())).copied()
+        }
+        None => candidates.first().copied(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(levels: &[(u64, u64)]) -> Vec<(Price, Quantity)> {
+        levels.iter().map(|(p, q)| (Price(*p), Quantity(*q))).collect()
+    }
+
+    #[test]
+    fn test_not_crossed() {
+        let bids = levels(&[(99, 10)]);
+        let asks = levels(&[(100, 10)]);
+        assert_eq!(equilibrium(&bids, &asks, Quantity(0), Quantity(0), None), None);
+        assert_eq!(equilibrium(&bids, &[], Quantity(0), Quantity(0), None), None);
+    }
+
+    #[test]
+    fn test_maximum_volume() {
+        let bids = levels(&[(102, 5), (101, 10), (100, 10)]);
+        let asks = levels(&[(99, 8), (100, 7), (101, 20)]);
+        let eq = equilibrium(&bids, &asks, Quantity(0), Quantity(0), None).unwrap();
+        // at 100: buy 25 / sell 15, at 101: buy 15 / sell 35
+        assert_eq!(eq.price, Price(100));
+        assert_eq!(eq.volume, Quantity(15));
+        assert_eq!(eq.buy_qty, Quantity(25));
+        assert_eq!(eq.sell_qty, Quantity(15));
+        assert_eq!(
+            eq.indicative(),
+            IndicativeAuction {
+                price: Price(100),
+                matched_qty: Quantity(15),
+                surplus_qty: Quantity(10),
+                surplus_side: Some(Side::Buy),
+            }
+        );
+    }
+
+    #[test]
+    fn test_minimum_imbalance() {
+        let bids = levels(&[(101, 10), (100, 2)]);
+        let asks = levels(&[(100, 10), (101, 5)]);
+        // volume 10 at both prices, surplus 2 at 100 and -5 at 101
+        let eq = equilibrium(&bids, &asks, Quantity(0), Quantity(0), None).unwrap();
+        assert_eq!(eq.price, Price(100));
+        assert_eq!(eq.surplus(), 2);
+    }
+
+    #[test]
+    fn test_market_pressure() {
+        // buy surplus at every price: the highest price
+        let bids = levels(&[(102, 20)]);
+        let asks = levels(&[(100, 5)]);
+        assert_eq!(
+            equilibrium(&bids, &asks, Quantity(0), Quantity(0), None).unwrap().price,
+            Price(102)
+        );
+
+        // sell surplus at every price: the lowest price
+        let bids = levels(&[(102, 5)]);
+        let asks = levels(&[(100, 20)]);
+        assert_eq!(
+            equilibrium(&bids, &asks, Quantity(0), Quantity(0), None).unwrap().price,
+            Price(100)
+        );
+    }
+
+    #[test]
+    fn test_reference_price() {
+        // balanced at every price between 100 and 104
+        let bids = levels(&[(104, 10)]);
+        let asks = levels(&[(100, 10)]);
+        assert_eq!(
+            equilibrium(&bids, &asks, Quantity(0), Quantity(0), None).unwrap().price,
+            Price(100)
+        );
+        assert_eq!(
+            equilibrium(&bids, &asks, Quantity(0), Quantity(0), Some(Price(103))).unwrap().price,
+            Price(104)
+        );
+        assert_eq!(
+            equilibrium(&bids, &asks, Quantity(0), Quantity(0), Some(Price(90))).unwrap().price,
+            Price(100)
+        );
+        let indicative =
+            equilibrium(&bids, &asks, Quantity(0), Quantity(0), None).unwrap().indicative();
+        assert_eq!(indicative.surplus_qty, Quantity(0));
+        assert_eq!(indicative.surplus_side, None);
+    }
+
+    #[test]
+    fn test_market_orders() {
+        // market orders trade at any price
+        let bids = levels(&[(101, 5)]);
+        let asks = levels(&[(100, 5), (102, 10)]);
+        let eq = equilibrium(&bids, &asks, Quantity(8), Quantity(0), None).unwrap();
+        assert_eq!(eq.price, Price(102));
+        assert_eq!(eq.volume, Quantity(8));
+        assert_eq!(eq.surplus(), -7);
+
+        // only market orders: the reference price
+        assert_eq!(equilibrium(&[], &[], Quantity(5), Quantity(3), None), None);
+        let eq = equilibrium(&[], &[], Quantity(5), Quantity(3), Some(Price(100))).unwrap();
+        assert_eq!(eq.price, Price(100));
+        assert_eq!(eq.volume, Quantity(3));
+    }
+}
@@ -17,18 +17,37 @@ use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::ops::{Add, Div, Sub};
 
+use serde::{Deserialize, Serialize};
+
+use crate::accounts::{Account, Accounts, Asset};
+use crate::auction::{equilibrium, Equilibrium, IndicativeAuction};
+use crate::candles::{Candle, CandleInterval, Candles};
+use crate::conflation::{ConflationPolicy, Conflator};
 use crate::enums::{JournalOp, OrderOptions};
+use crate::events::{OrderBookEvent, OrderBookListener};
+use crate::fees::{fee, FeeSchedule, Fees};
+use crate::instrument::InstrumentSpec;
 use crate::journal::Snapshot;
-use crate::order::{OrderId, Price, Quantity};
+use crate::market_data::{BboUpdate, DepthSnapshot, DepthUpdate, L3Message, L3Update, TopOfBook};
+use crate::order::{OrderId, OwnerId, Price, Quantity, SessionId};
+use crate::positions::{Position, Positions};
 use crate::report::ExecutionReportParams;
+use crate::risk::{
+    notional, InterruptionPhase, InterruptionRemainder, PriceBand, PriceBandAction, ReferencePrice,
+    RiskLimits, VolatilityInterruption,
+};
+use crate::throttle::{Throttle, ThrottleActivity, ThrottleLimits};
+use crate::trade::{Trade, TradeId, DEFAULT_TRADE_HISTORY};
 use crate::utils::{current_timestamp_millis, safe_add};
 use crate::{
     error::{make_error, ErrorType, Result},
     journal::JournalLog,
-    order::{LimitOrder, LimitOrderOptions, MarketOrder, MarketOrderOptions},
-    {OrderStatus, OrderType, Side, TimeInForce},
+    order::{
+        get_order_time_in_force, LimitOrder, LimitOrderOptions, MarketOrder, MarketOrderOptions,
+    },
+    {OrderStatus, OrderType, Side, TimeInForce, TradingPhase},
 };
-use crate::{ExecutionReport, FillReport};
+use crate::{ExecutionReport, FillReport, UncrossReport};
 use std::collections::VecDeque;
 
 /// Configuration options for initializing a new [`OrderBook`].
@@ -40,17 +59,79 @@ use std::collections::VecDeque;
 ///   of an order book at a given point in time.
 /// - `replay_logs`: A vector of [`JournalLog`] entries to replay. Logs should ideally be in
 ///   chronological order (`op_id` ascending), but `replay_logs` will sort them internally.
+/// - `risk_limits`: Optional [`RiskLimits`] checked before any order is validated.
+/// - `price_band`: Optional [`PriceBand`] around a reference price.
+/// - `instrument`: Optional [`InstrumentSpec`] every order is validated against.
+/// - `volatility_interruption`: Optional [`VolatilityInterruption`] stopping matching
+///   outside a range around a reference price.
+/// - `accounts`: If `true`, every order must belong to an owner with enough free balance.
+///   Defaults to `false`.
+/// - `positions`: If `true`, the position and PnL of every owner are tracked from fills.
+///   Defaults to `false`.
+/// - `order_attribution`: If `true`, the market-by-order feed discloses the owner of
+///   every order.
+///   Defaults to `false`.
+/// - `fees`: Optional maker/taker [`FeeSchedule`] charged on every fill.
+/// - `throttle`: Optional per-owner [`ThrottleLimits`] checked before any other check.
+/// - `trade_history`: Number of recent trades kept by the book.
+///   Defaults to 1 000.
+/// - `candles`: The [`CandleInterval`]s of the OHLCV candles aggregated from the trades.
+///   Defaults to none.
+/// - `conflation`: Optional [`ConflationPolicy`] of the conflated level-2 feed.
 #[derive(Debug, Clone, Default)]
 pub struct OrderBookOptions {
     pub journaling: bool,
     pub snapshot: Option<Snapshot>,
     pub replay_logs: Option<Vec<JournalLog>>,
+    pub risk_limits: Option<RiskLimits>,
+    pub price_band: Option<PriceBand>,
+    pub instrument: Option<InstrumentSpec>,
+    pub volatility_interruption: Option<VolatilityInterruption>,
+    pub accounts: bool,
+    pub positions: bool,
+    pub order_attribution: bool,
+    pub fees: Option<FeeSchedule>,
+    pub throttle: Option<ThrottleLimits>,
+    pub trade_history: Option<usize>,
+    pub candles: Vec<CandleInterval>,
+    pub conflation: Option<ConflationPolicy>,
 }
 
-#[derive(Debug, PartialEq)]
+/// Aggregated price levels of both sides of the book, best level first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Depth {
-    pub asks: Vec<(Price, Quantity)>, // (price, volume)
-    pub bids: Vec<(Price, Quantity)>, // (price, volume)
+    pub asks: Vec<DepthLevel>,
+    pub bids: Vec<DepthLevel>,
+}
+
+/// A price level of the [`Depth`].
+///
+/// # Fields
+/// - `price`: Price of the level
+/// - `quantity`: Total open quantity of the level
+/// - `order_count`: Number of orders resting at the level
+/// - `cumulative_qty`: Total open quantity from the best level up to this one, included
+/// - `cumulative_notional`: Total notional (`price × quantity`) from the best level up to
+///   this one, included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepthLevel {
+    pub price: Price,
+    pub quantity: Quantity,
+    pub order_count: usize,
+    pub cumulative_qty: Quantity,
+    pub cumulative_notional: u128,
+}
+
+/// Size of the price buckets of [`OrderBook::grouped_depth`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthGrouping {
+    /// Buckets of `n` ticks, using the tick size of the [`InstrumentSpec`] at
+    /// the price of each level, or a tick of `1` if none is configured. The
+    /// buckets of a [`TickTable`](crate::TickTable) tier start at its first
+    /// price and never spill over the next tier.
+    Ticks(u64),
+    /// Buckets of a fixed price step.
+    Step(Price),
 }
 
 /// A limit order book implementation with support for market orders,
@@ -66,6 +147,42 @@ pub struct OrderBook {
     pub(crate) asks: BTreeMap<Price, VecDeque<OrderId>>,
     pub(crate) bids: BTreeMap<Price, VecDeque<OrderId>>,
     pub(crate) journaling: bool,
+    pub(crate) risk_limits: Option<RiskLimits>,
+    /// Number of resting orders per owner, used by the risk checks
+    pub(crate) open_orders: HashMap<OwnerId, usize>,
+    pub(crate) price_band: Option<PriceBand>,
+    pub(crate) instrument: Option<InstrumentSpec>,
+    pub(crate) volatility_interruption: Option<VolatilityInterruption>,
+    pub(crate) last_trade_price: Option<Price>,
+    pub(crate) reference_price: Option<Price>,
+    pub(crate) accounts: Option<Accounts>,
+    pub(crate) positions: Option<Positions>,
+    pub(crate) fees: Option<Fees>,
+    pub(crate) throttle: Option<Throttle>,
+    /// Fixed engine time, `None` to follow the system clock
+    pub(crate) clock: Option<i64>,
+    pub(crate) phase: TradingPhase,
+    /// Auction-only orders waiting for their auction, outside the book
+    pub(crate) auction_orders: HashMap<OrderId, LimitOrder>,
+    pub(crate) listeners: Vec<Box<dyn OrderBookListener>>,
+    /// Sequence number of the last level-2 update
+    pub(crate) depth_sequence: u64,
+    /// Sequence number of the last market-by-order message
+    pub(crate) l3_sequence: u64,
+    /// Sequence number of the last top of book change
+    pub(crate) bbo_sequence: u64,
+    /// Top of the book at the end of the last operation
+    pub(crate) last_bbo: TopOfBook,
+    pub(crate) next_trade_id: TradeId,
+    /// Recent trades, oldest first
+    pub(crate) trades: VecDeque<Trade>,
+    /// Maximum number of recent trades kept
+    pub(crate) trade_history: usize,
+    pub(crate) candles: Option<Candles>,
+    pub(crate) conflation: Option<Conflator>,
+    pub(crate) order_attribution: bool,
+    /// Order being replaced by a modify, reported once the new order rests
+    pub(crate) replacing: Option<OrderId>,
 }
 
 impl OrderBook {
@@ -91,6 +208,34 @@ impl OrderBook {
             asks: BTreeMap::new(),
             bids: BTreeMap::new(),
             journaling: opts.journaling,
+            risk_limits: opts.risk_limits,
+            open_orders: HashMap::new(),
+            price_band: opts.price_band,
+            instrument: opts.instrument,
+            volatility_interruption: opts.volatility_interruption,
+            last_trade_price: None,
+            reference_price: None,
+            accounts: opts
+                .accounts
+                .then(|| Accounts::new(opts.fees.as_ref().map_or(0, |f| f.max_bps()))),
+            positions: opts.positions.then(Positions::default),
+            order_attribution: opts.order_attribution,
+            fees: opts.fees.map(Fees::new),
+            throttle: opts.throttle.map(Throttle::new),
+            clock: None,
+            phase: TradingPhase::default(),
+            auction_orders: HashMap::new(),
+            listeners: Vec::new(),
+            depth_sequence: 0,
+            l3_sequence: 0,
+            bbo_sequence: 0,
+            last_bbo: TopOfBook::default(),
+            next_trade_id: TradeId(0),
+            trades: VecDeque::new(),
+            trade_history: opts.trade_history.unwrap_or(DEFAULT_TRADE_HISTORY),
+            candles: (!opts.candles.is_empty()).then(|| Candles::new(&opts.candles)),
+            conflation: opts.conflation.map(Conflator::new),
+            replacing: None,
         }
     }
 
@@ -99,6 +244,39 @@ impl OrderBook {
         &self.symbol
     }
 
+    /// Get the current engine time in milliseconds.
+    ///
+    /// The engine clock follows the system clock unless it has been fixed with
+    /// [`OrderBook::set_clock`]. While replaying a journal it is set to the
+    /// timestamp of each log, so that time-dependent features behave exactly
+    /// like in the original run.
+    pub fn now(&self) -> i64 {
+        self.clock.unwrap_or_else(current_timestamp_millis)
+    }
+
+    /// Fixes the engine clock to `ts` milliseconds, or makes it follow the
+    /// system clock again with `None`.
+    ///
+    /// Moving the clock closes the candles that ended before the new time and
+    /// flushes the conflated level-2 updates whose interval has elapsed.
+    pub fn set_clock(&mut self, ts: Option<i64>) {
+        self.clock = ts;
+        if let Some(now) = ts {
+            let closed = self.candles.as_mut().map_or_else(Vec::new, |c| c.roll(now));
+            for candle in closed {
+                self.emit(OrderBookEvent::CandleClosed(candle));
+            }
+            self.publish_conflated(false);
+        }
+    }
+
+    /// Publishes the pending conflated level-2 updates now, whatever the
+    /// [`ConflationPolicy`]. Does nothing if conflation is disabled or no
+    /// update is pending.
+    pub fn flush_conflated(&mut self) {
+        self.publish_conflated(true);
+    }
+
     /// Executes a market order against the order book.
     ///
     /// The order will immediately match with the best available opposite orders
@@ -111,11 +289,32 @@ impl OrderBook {
     /// An [`ExecutionReport`] with fill information and remaining quantity, if any.
     ///
     /// # Errors
-    /// Returns `Err` if the input is invalid (e.g., size is zero).
+    /// Returns `Err` if the order violates the configured [`RiskLimits`] or if the input
+    /// is invalid (e.g., size is zero).
     pub fn market(&mut self, options: MarketOrderOptions) -> Result<ExecutionReport> {
+        let report = self.submit_market(options);
+        self.emit_rejection(OrderType::Market, options.side, options.owner, &report);
+        self.publish_market_data();
+        report
+    }
+
+    fn submit_market(&mut self, options: MarketOrderOptions) -> Result<ExecutionReport> {
+        self.check_phase(JournalOp::Market)?;
+        self.check_throttle(options.owner, true)?;
+        self.check_market_order_risk(&options)?;
         self.validate_market_order(&options)?;
+        self.check_market_order_funds(&options)?;
 
+        self.record_message(options.owner, true);
         let mut order = MarketOrder::new(self.new_order_id(), options);
+        self.emit(OrderBookEvent::OrderAccepted {
+            id: order.id,
+            order_type: OrderType::Market,
+            side: order.side,
+            price: None,
+            quantity: order.orig_qty,
+            owner: options.owner,
+        });
         let mut report = ExecutionReport::new(ExecutionReportParams {
             id: order.id,
             order_type: OrderType::Market,
@@ -127,12 +326,29 @@ impl OrderBook {
             post_only: false,
         });
 
+        // Market sweeps stop at the price band edge, if any
+        let band = self.price_band_edges();
         let mut fills = Vec::new();
-        let remaining_qty = match order.side {
-            Side::Buy => self.match_with_asks(order.remaining_qty(), &mut fills, None),
-            Side::Sell => self.match_with_bids(order.remaining_qty(), &mut fills, None),
+        let (remaining_qty, interrupted) = match order.side {
+            Side::Buy => self.match_with_asks(order.remaining_qty(), &mut fills, band.map(|b| b.1)),
+            Side::Sell => {
+                self.match_with_bids(order.remaining_qty(), &mut fills, band.map(|b| b.0))
+            }
         };
+        report.fees =
+            self.settle_fills(order.side, options.owner, None, order.orig_qty, &mut fills);
+        self.emit_fills(order.id, order.side, order.orig_qty, &fills);
+        if interrupted {
+            self.interrupt();
+        }
         order.executed_qty = order.orig_qty.sub(remaining_qty);
+        if remaining_qty.value() > 0 {
+            self.emit(OrderBookEvent::OrderCanceled {
+                id: order.id,
+                side: order.side,
+                remaining_qty,
+            });
+        }
         order.status = if order.remaining_qty().value() > 0 {
             OrderStatus::PartiallyFilled
         } else {
@@ -143,12 +359,13 @@ impl OrderBook {
         report.executed_qty = order.executed_qty;
         report.status = order.status;
         report.taker_qty = order.executed_qty;
+        report.fills = fills;
 
         if self.journaling {
             self.last_op = safe_add(self.last_op, 1);
             report.log = Some(JournalLog {
                 op_id: self.last_op,
-                ts: current_timestamp_millis(),
+                ts: self.now(),
                 op: JournalOp::Market,
                 o: OrderOptions::Market(options),
             })
@@ -157,7 +374,7 @@ impl OrderBook {
         Ok(report)
     }
     pub fn market_raw(&mut self, side: Side, quantity: u64) -> Result<ExecutionReport> {
-        self.market(MarketOrderOptions { side, quantity: Quantity(quantity) })
+        self.market(MarketOrderOptions::new(side, quantity))
     }
 
     /// Submits a new limit order to the order book.
@@ -172,11 +389,43 @@ impl OrderBook {
     /// An [`ExecutionReport`] with match information and resting status.
     ///
     /// # Errors
-    /// Returns `Err` if the input is invalid.
+    /// Returns `Err` if the order violates the configured [`RiskLimits`] or
+    /// [`PriceBand`], or if the input is invalid.
     pub fn limit(&mut self, options: LimitOrderOptions) -> Result<ExecutionReport> {
-        self.validate_limit_order(&options)?;
+        let report = self.limit_order(options);
+        self.publish_market_data();
+        report
+    }
+
+    fn limit_order(&mut self, options: LimitOrderOptions) -> Result<ExecutionReport> {
+        let report = self.submit_limit(options);
+        self.emit_rejection(OrderType::Limit, options.side, options.owner, &report);
+        report
+    }
 
-        let mut order = LimitOrder::new(self.new_order_id(), options);
+    fn submit_limit(&mut self, options: LimitOrderOptions) -> Result<ExecutionReport> {
+        self.check_phase(JournalOp::Limit)?;
+        if self.phase.is_auction()
+            && get_order_time_in_force(options.time_in_force) != TimeInForce::GTC
+        {
+            return Err(make_error(ErrorType::OrderNotAllowedInPhase));
+        }
+        self.check_throttle(options.owner, true)?;
+        self.check_limit_order_risk(&options)?;
+        let order_options = self.apply_price_band(options)?;
+        self.validate_limit_order(&order_options)?;
+        self.reserve_limit_order_funds(&order_options)?;
+
+        self.record_message(order_options.owner, true);
+        let mut order = LimitOrder::new(self.new_order_id(), order_options, self.now());
+        self.emit(OrderBookEvent::OrderAccepted {
+            id: order.id,
+            order_type: OrderType::Limit,
+            side: order.side,
+            price: Some(order.price),
+            quantity: order.orig_qty,
+            owner: order.owner,
+        });
         let mut report = ExecutionReport::new(ExecutionReportParams {
             id: order.id,
             order_type: OrderType::Limit,
@@ -188,30 +437,61 @@ impl OrderBook {
             post_only: order.post_only,
         });
 
+        // Orders are only collected during an auction
         let mut fills = Vec::new();
-        let remaining_qty = match order.side {
-            Side::Buy => self.match_with_asks(order.remaining_qty(), &mut fills, Some(order.price)),
-            Side::Sell => {
+        let (remaining_qty, interrupted) = match (self.phase, order.side) {
+            (phase, _) if phase.is_auction() => (order.remaining_qty(), false),
+            (_, Side::Buy) => {
+                self.match_with_asks(order.remaining_qty(), &mut fills, Some(order.price))
+            }
+            (_, Side::Sell) => {
                 self.match_with_bids(order.remaining_qty(), &mut fills, Some(order.price))
             }
         };
+        report.fees = self.settle_fills(
+            order.side,
+            order.owner,
+            Some(order.price),
+            order.orig_qty,
+            &mut fills,
+        );
+        self.emit_fills(order.id, order.side, order.orig_qty, &fills);
         order.executed_qty = order.orig_qty.sub(remaining_qty);
         order.taker_qty = order.orig_qty.sub(order.remaining_qty());
         order.maker_qty = order.remaining_qty();
+        // A remainder left resting in a halted book would cross it
+        let cancel_remainder = interrupted
+            && (order.time_in_force == TimeInForce::FOK
+                || self.volatility_interruption.is_some_and(|vi| {
+                    vi.remainder == InterruptionRemainder::Cancel
+                        || vi.phase == InterruptionPhase::Halted
+                }));
+        if interrupted {
+            self.interrupt();
+        }
 
         if order.remaining_qty().value() > 0 {
-            if order.time_in_force == TimeInForce::IOC {
+            if order.time_in_force == TimeInForce::IOC || cancel_remainder {
                 // If IOC order was not matched completely so set as canceled
                 // and don't insert the order in the order book
                 order.status = OrderStatus::Canceled;
+                self.release_order_funds(&order);
+                self.emit(OrderBookEvent::OrderCanceled {
+                    id: order.id,
+                    side: order.side,
+                    remaining_qty: order.remaining_qty(),
+                });
             } else {
                 order.status = OrderStatus::PartiallyFilled;
                 self.orders.insert(order.id, order);
-                if order.side == Side::Buy {
-                    self.bids.entry(order.price).or_default().push_back(order.id);
-                } else {
-                    self.asks.entry(order.price).or_default().push_back(order.id);
-                }
+                self.track_open_order(order.owner);
+                self.emit(OrderBookEvent::OrderRested {
+                    id: order.id,
+                    side: order.side,
+                    price: order.price,
+                    quantity: order.remaining_qty(),
+                });
+                self.push_to_level(order.side, order.price, order.id);
             }
         } else {
             order.status = OrderStatus::Filled;
@@ -222,12 +502,13 @@ impl OrderBook {
         report.taker_qty = order.taker_qty;
         report.maker_qty = order.maker_qty;
         report.status = order.status;
+        report.fills = fills;
 
         if self.journaling {
             self.last_op = safe_add(self.last_op, 1);
             report.log = Some(JournalLog {
                 op_id: self.last_op,
-                ts: current_timestamp_millis(),
+                ts: self.now(),
                 op: JournalOp::Limit,
                 o: OrderOptions::Limit(options),
             })
@@ -249,9 +530,107 @@ impl OrderBook {
             price: Price(price),
             time_in_force,
             post_only,
+            owner: None,
+            session: None,
+            persist: false,
         })
     }
 
+    /// Submits a market-on-open (MOO) order.
+    ///
+    /// The order stays dormant outside the book until the opening auction is
+    /// uncrossed in the [`TradingPhase::PreOpen`] phase, where it trades at the
+    /// auction price ahead of every limit order. Any unfilled remainder is
+    /// canceled after the auction.
+    ///
+    /// # Errors
+    /// Returns `Err` if the trading phase does not accept orders, or if the
+    /// order is invalid. Market-on-open buy orders are rejected when accounts
+    /// are enabled, since their price is unknown until the auction.
+    pub fn market_on_open(&mut self, options: MarketOrderOptions) -> Result<ExecutionReport> {
+        self.auction_only_order(
+            JournalOp::MarketOnOpen,
+            OrderType::MarketOnOpen,
+            market_auction_options(&options),
+            OrderOptions::MarketOnOpen(options),
+        )
+    }
+
+    /// Submits a limit-on-open (LOO) order.
+    ///
+    /// The order stays dormant outside the book until the opening auction is
+    /// uncrossed in the [`TradingPhase::PreOpen`] phase. Any unfilled remainder
+    /// is canceled after the auction.
+    ///
+    /// # Errors
+    /// Returns `Err` if the trading phase does not accept orders, or if the
+    /// order is invalid.
+    pub fn limit_on_open(&mut self, options: LimitOrderOptions) -> Result<ExecutionReport> {
+        self.auction_only_order(
+            JournalOp::LimitOnOpen,
+            OrderType::LimitOnOpen,
+            options,
+            OrderOptions::LimitOnOpen(options),
+        )
+    }
+
+    /// Submits a market-on-close (MOC) order.
+    ///
+    /// Same as [`OrderBook::market_on_open`], for the closing auction uncrossed
+    /// in the [`TradingPhase::ClosingAuction`] phase.
+    pub fn market_on_close(&mut self, options: MarketOrderOptions) -> Result<ExecutionReport> {
+        self.auction_only_order(
+            JournalOp::MarketOnClose,
+            OrderType::MarketOnClose,
+            market_auction_options(&options),
+            OrderOptions::MarketOnClose(options),
+        )
+    }
+
+    /// Submits a limit-on-close (LOC) order.
+    ///
+    /// Same as [`OrderBook::limit_on_open`], for the closing auction uncrossed
+    /// in the [`TradingPhase::ClosingAuction`] phase.
+    pub fn limit_on_close(&mut self, options: LimitOrderOptions) -> Result<ExecutionReport> {
+        self.auction_only_order(
+            JournalOp::LimitOnClose,
+            OrderType::LimitOnClose,
+            options,
+            OrderOptions::LimitOnClose(options),
+        )
+    }
+
+    /// Cancels every resting order of a session, except the ones submitted
+    /// with `persist` set.
+    ///
+    /// Orders are canceled one by one in ascending ID order, in every trading
+    /// phase and regardless of the throttling limits. Each cancellation is
+    /// journaled as a `session_cancel` operation.
+    ///
+    /// # Parameters
+    /// - `session`: The session to close
+    ///
+    /// # Returns
+    /// An [`ExecutionReport`] for every canceled order.
+    pub fn close_session(&mut self, session: SessionId) -> Vec<ExecutionReport> {
+        let mut ids: Vec<OrderId> = self
+            .orders
+            .values()
+            .chain(self.auction_orders.values())
+            .filter(|o| o.session == Some(session) && !o.persist)
+            .map(|o| o.id)
+            .collect();
+        ids.sort_by_key(|id| id.0);
+        // Closing a session is not a message of the owner: it is neither
+        // throttled nor counted, and applies in every trading phase
+        let reports = ids
+            .into_iter()
+            .filter_map(|id| self.remove_canceled(id, JournalOp::SessionCancel).ok())
+            .collect();
+        self.publish_market_data();
+        reports
+    }
+
     /// Cancels an existing order by ID.
     ///
     /// # Parameters
@@ -263,26 +642,33 @@ impl OrderBook {
     /// # Errors
     /// Returns `Err` if the order is not found.
     pub fn cancel(&mut self, id: OrderId) -> Result<ExecutionReport> {
-        let mut order = match self.orders.remove(&id) {
+        let report = self.cancel_order(id);
+        self.publish_market_data();
+        report
+    }
+
+    fn cancel_order(&mut self, id: OrderId) -> Result<ExecutionReport> {
+        self.check_phase(JournalOp::Cancel)?;
+        let owner = self.find_order(id).and_then(|o| o.owner);
+        self.check_throttle(owner, false)?;
+        let report = self.remove_canceled(id, JournalOp::Cancel)?;
+        self.record_message(owner, false);
+        Ok(report)
+    }
+
+    /// Cancels an order without checking the trading phase and the throttling
+    /// limits, and journals it as `op`.
+    fn remove_canceled(&mut self, id: OrderId, op: JournalOp) -> Result<ExecutionReport> {
+        let mut order = match self.remove_order(id) {
             Some(o) => o,
             None => return Err(make_error(ErrorType::OrderNotFound)),
         };
-
-        let book_side = match order.side {
-            Side::Buy => &mut self.bids,
-            Side::Sell => &mut self.asks,
-        };
-
-        if let Some(queue) = book_side.get_mut(&order.price) {
-            if let Some(pos) = queue.iter().position(|x| *x == id) {
-                queue.remove(pos);
-            }
-            if queue.is_empty() {
-                book_side.remove(&order.price);
-            }
-        }
-
         order.status = OrderStatus::Canceled;
+        self.emit(OrderBookEvent::OrderCanceled {
+            id: order.id,
+            side: order.side,
+            remaining_qty: order.remaining_qty(),
+        });
 
         let mut report = ExecutionReport {
             order_id: order.id,
@@ -298,6 +684,7 @@ impl OrderBook {
             time_in_force: order.time_in_force,
             post_only: order.post_only,
             fills: Vec::new(),
+            fees: 0,
             log: None,
         };
 
@@ -305,8 +692,8 @@ impl OrderBook {
             self.last_op = safe_add(self.last_op, 1);
             report.log = Some(JournalLog {
                 op_id: self.last_op,
-                ts: current_timestamp_millis(),
-                op: JournalOp::Cancel,
+                ts: self.now(),
+                op,
                 o: OrderOptions::Cancel(order.id),
             })
         }
@@ -334,7 +721,8 @@ impl OrderBook {
     /// An [`ExecutionReport`] describing the new order created.
     ///
     /// # Errors
-    /// Returns `Err` if the order is not found or if the modification parameters are invalid.
+    /// Returns `Err` if the order is not found, is an auction-only order waiting
+    /// for its auction, or if the modification parameters are invalid.
     ///
     /// # Note
     /// This is a full replacement: time-priority is reset and the order ID changes.
@@ -344,61 +732,121 @@ impl OrderBook {
         price: Option<Price>,
         quantity: Option<Quantity>,
     ) -> Result<ExecutionReport> {
+        let (owner, session, persist) =
+            self.orders.get(&id).map_or((None, None, false), |o| (o.owner, o.session, o.persist));
+        self.check_phase(JournalOp::Modify)?;
+        let Some(order) = self.orders.get(&id).copied() else {
+            if self.auction_orders.contains_key(&id) {
+                return Err(make_error(ErrorType::AuctionOrderNotModifiable));
+            }
+            return Err(make_error(ErrorType::OrderNotFound));
+        };
+        // Validated before canceling, so that an invalid modify keeps the order.
+        // A modify is throttled as a single order message.
+        let checked = self
+            .check_replacement(&order, price, quantity)
+            .and_then(|_| self.check_throttle(owner, true));
+        if let Err(error) = checked {
+            self.emit(OrderBookEvent::OrderRejected {
+                order_type: OrderType::Limit,
+                side: order.side,
+                owner,
+                error: error.clone(),
+            });
+            return Err(error);
+        }
+        let throttle = self.throttle.take();
         let old_journaling = self.journaling;
         // Temporary disable journaling
         self.journaling = false;
-        let report = match self.cancel(id) {
+        self.replacing = Some(id);
+        let report = match self.cancel_order(id) {
             Ok(o) => o,
             Err(e) => {
                 // Restore previous journaling value before returning
                 self.journaling = old_journaling;
+                self.throttle = throttle;
+                self.replacing = None;
                 return Err(e);
             }
         };
 
+        let (side, old_price, old_qty) = (report.side, report.price, report.remaining_qty);
         let mut report = match (price, quantity) {
-            (None, Some(quantity)) => self.limit(LimitOrderOptions {
+            (None, Some(quantity)) => self.limit_order(LimitOrderOptions {
                 side: report.side,
                 quantity,
                 price: report.price,
                 time_in_force: Some(report.time_in_force),
                 post_only: Some(report.post_only),
+                owner,
+                session,
+                persist,
             }),
-            (Some(price), None) => self.limit(LimitOrderOptions {
+            (Some(price), None) => self.limit_order(LimitOrderOptions {
                 side: report.side,
                 quantity: report.remaining_qty,
                 price,
                 time_in_force: Some(report.time_in_force),
                 post_only: Some(report.post_only),
+                owner,
+                session,
+                persist,
             }),
-            (Some(price), Some(quantity)) => self.limit(LimitOrderOptions {
+            (Some(price), Some(quantity)) => self.limit_order(LimitOrderOptions {
                 side: report.side,
                 quantity,
                 price,
                 time_in_force: Some(report.time_in_force),
                 post_only: Some(report.post_only),
+                owner,
+                session,
+                persist,
             }),
             (None, None) => {
                 // Restore previous journaling value before returning
                 self.journaling = old_journaling;
+                self.throttle = throttle;
+                self.replacing = None;
+                self.emit_l3(L3Message::Delete { id, side, price: old_price, quantity: old_qty });
+                self.publish_market_data();
                 return Err(make_error(ErrorType::InvalidPriceOrQuantity));
             }
         };
 
         // Restore previous journaling value
         self.journaling = old_journaling;
+        self.throttle = throttle;
+        // The new order did not rest: the old one is simply deleted
+        if self.replacing.take().is_some() {
+            self.emit_l3(L3Message::Delete { id, side, price: old_price, quantity: old_qty });
+        }
+        if let Ok(r) = report.as_ref() {
+            self.record_message(owner, true);
+            for fill in &r.fills {
+                self.record_trade(fill.owner);
+                self.record_trade(owner);
+            }
+        }
 
         if let Ok(r) = report.as_mut() {
+            self.emit(OrderBookEvent::OrderModified {
+                id,
+                new_id: r.order_id,
+                price: r.price,
+                quantity: r.orig_qty,
+            });
             if self.journaling {
                 self.last_op = safe_add(self.last_op, 1);
                 r.log = Some(JournalLog {
                     op_id: self.last_op,
-                    ts: current_timestamp_millis(),
+                    ts: self.now(),
                     op: JournalOp::Modify,
                     o: OrderOptions::Modify { id, price, quantity },
                 });
             }
         }
+        self.publish_market_data();
         report
     }
 
@@ -430,7 +878,7 @@ impl OrderBook {
     }
 
     pub fn get_order(&self, id: OrderId) -> Result<LimitOrder> {
-        match self.orders.get(&id) {
+        match self.find_order(id) {
             Some(o) => Ok(*o),
             None => Err(make_error(ErrorType::OrderNotFound)),
         }
@@ -446,6 +894,35 @@ impl OrderBook {
         self.asks.first_key_value().map(|(price, _)| *price)
     }
 
+    /// Get the best bid and best ask with the total quantity of their levels
+    pub fn top_of_book(&self) -> TopOfBook {
+        TopOfBook {
+            bid: self.best_bid().map(|price| (price, self.level_qty(Side::Buy, price))),
+            ask: self.best_ask().map(|price| (price, self.level_qty(Side::Sell, price))),
+        }
+    }
+
+    /// Get the most recent trades, newest first.
+    ///
+    /// # Parameters
+    /// - `limit`: Maximum number of trades returned, every recent trade if `None`
+    pub fn recent_trades(&self, limit: Option<usize>) -> Vec<Trade> {
+        self.trades.iter().rev().take(limit.unwrap_or(usize::MAX)).copied().collect()
+    }
+
+    /// Get the recent trades executed after the trade `id`, oldest first.
+    ///
+    /// Trades evicted from the recent trades buffer are not returned.
+    pub fn trades_since(&self, id: TradeId) -> Vec<Trade> {
+        self.trades.iter().filter(|t| t.id > id).copied().collect()
+    }
+
+    /// Get the open candle of `interval`, if candle aggregation is enabled for
+    /// it and a trade was executed since the last candle was closed.
+    pub fn candle(&self, interval: CandleInterval) -> Option<Candle> {
+        self.candles.as_ref().and_then(|c| c.open.iter().find(|c| c.interval == interval).copied())
+    }
+
     /// Get the mid price (average of best bid and best ask)
     pub fn mid_price(&self) -> Option<Price> {
         match (self.best_bid(), self.best_ask()) {
@@ -454,6 +931,259 @@ impl OrderBook {
         }
     }
 
+    /// Get the price of the last trade executed by the book, if any
+    pub fn last_trade_price(&self) -> Option<Price> {
+        self.last_trade_price
+    }
+
+    /// Get the external reference price used by the price band, if any
+    pub fn reference_price(&self) -> Option<Price> {
+        self.reference_price
+    }
+
+    /// Sets the external reference price used by a [`PriceBand`] configured
+    /// with [`ReferencePrice::External`] and by the static range of a
+    /// [`VolatilityInterruption`].
+    ///
+    /// # Parameters
+    /// - `price`: The new reference price
+    ///
+    /// # Returns
+    /// The [`JournalLog`] of the operation if journaling is enabled.
+    ///
+    /// # Errors
+    /// Returns `Err` if the price is zero.
+    pub fn set_reference_price(&mut self, price: Price) -> Result<Option<JournalLog>> {
+        if price.value() == 0 {
+            return Err(make_error(ErrorType::InvalidPrice));
+        }
+        self.reference_price = Some(price);
+        Ok(self.journal(JournalOp::SetReferencePrice, OrderOptions::SetReferencePrice(price)))
+    }
+
+    /// Get the current trading phase
+    pub fn phase(&self) -> TradingPhase {
+        self.phase
+    }
+
+    /// Moves the order book to another trading phase.
+    ///
+    /// # Parameters
+    /// - `phase`: The new [`TradingPhase`]
+    ///
+    /// # Returns
+    /// The [`JournalLog`] of the operation if journaling is enabled.
+    ///
+    /// # Errors
+    /// Returns `Err` if the transition is not allowed, see
+    /// [`TradingPhase::can_transition_to`].
+    pub fn set_phase(&mut self, phase: TradingPhase) -> Result<Option<JournalLog>> {
+        if !self.phase.can_transition_to(phase) {
+            return Err(make_error(ErrorType::InvalidPhaseTransition));
+        }
+        self.phase = phase;
+        Ok(self.journal(JournalOp::SetPhase, OrderOptions::SetPhase(phase)))
+    }
+
+    /// Uncrosses the book at the auction price.
+    ///
+    /// Every crossing order is executed at a single equilibrium price: the price
+    /// that maximizes the executed volume, then minimizes the imbalance, then
+    /// follows the market pressure, and finally is the closest to the reference
+    /// price (the external reference price or the last trade price). Orders are
+    /// executed in price-time priority.
+    ///
+    /// The trading phase is not changed: use [`OrderBook::set_phase`] to resume
+    /// continuous trading.
+    ///
+    /// # Returns
+    /// An [`UncrossReport`] with the auction price and the fills of every order.
+    ///
+    /// # Errors
+    /// Returns `Err` if the book is not in an auction phase.
+    pub fn uncross(&mut self) -> Result<UncrossReport> {
+        if !self.phase.is_auction() {
+            return Err(make_error(ErrorType::NotInAuction));
+        }
+        let mut report = UncrossReport {
+            price: None,
+            quantity: Quantity(0),
+            fills: Vec::new(),
+            canceled: Vec::new(),
+            log: None,
+        };
+
+        let equilibrium = self.auction_equilibrium();
+        let mut auction_ids: Vec<OrderId> = self
+            .auction_orders
+            .values()
+            .filter(|o| o.order_type.auction_phase() == Some(self.phase))
+            .map(|o| o.id)
+            .collect();
+        auction_ids.sort_by_key(|id| id.0);
+
+        if let Some(eq) = equilibrium {
+            report.price = Some(eq.price);
+            report.quantity = eq.volume;
+
+            // The auction-only orders of this auction join the matching: market
+            // orders trade at the auction price, ahead of every limit order.
+            let mut buys = VecDeque::new();
+            let mut sells = VecDeque::new();
+            for id in &auction_ids {
+                let Some(mut order) = self.auction_orders.remove(id) else { continue };
+                if order.order_type.is_market() {
+                    order.price = eq.price;
+                    match order.side {
+                        Side::Buy => buys.push_back(order.id),
+                        Side::Sell => sells.push_back(order.id),
+                    }
+                }
+                self.orders.insert(order.id, order);
+                if !order.order_type.is_market() {
+                    self.push_to_level(order.side, order.price, order.id);
+                }
+            }
+            buys.extend(self.bids.range(eq.price..).rev().flat_map(|(_, q)| q.iter().copied()));
+            sells.extend(self.asks.range(..=eq.price).flat_map(|(_, q)| q.iter().copied()));
+
+            let mut quantity_left = eq.volume;
+            while quantity_left.value() > 0 {
+                let (Some(buy), Some(sell)) = (buys.front().copied(), sells.front().copied())
+                else {
+                    break;
+                };
+                let fill_qty = self.orders[&buy]
+                    .remaining_qty()
+                    .min(self.orders[&sell].remaining_qty())
+                    .min(quantity_left);
+                report.fills.push(self.execute_auction_fill(buy, eq.price, fill_qty));
+                report.fills.push(self.execute_auction_fill(sell, eq.price, fill_qty));
+                let (maker, taker) = if buy.0 < sell.0 { (buy, sell) } else { (sell, buy) };
+                self.push_trade(maker, taker, None, eq.price, fill_qty);
+                quantity_left = quantity_left.sub(fill_qty);
+                if !self.orders.contains_key(&buy) {
+                    buys.pop_front();
+                }
+                if !self.orders.contains_key(&sell) {
+                    sells.pop_front();
+                }
+            }
+            self.last_trade_price = Some(eq.price);
+        }
+
+        // Unfilled remainders of auction-only orders are canceled
+        for id in auction_ids {
+            if let Some(order) = self.remove_order(id) {
+                self.emit(OrderBookEvent::OrderCanceled {
+                    id: order.id,
+                    side: order.side,
+                    remaining_qty: order.remaining_qty(),
+                });
+                report.canceled.push(order.id);
+            }
+        }
+
+        report.log = self.journal(JournalOp::Uncross, OrderOptions::Uncross);
+        self.publish_market_data();
+        Ok(report)
+    }
+
+    /// Get the indicative auction price, matched volume and surplus, computed
+    /// as if the book was uncrossed now. The book is not modified.
+    ///
+    /// Returns `None` if the book is not in an auction phase or is not crossed.
+    pub fn indicative_auction(&self) -> Option<IndicativeAuction> {
+        if !self.phase.is_auction() {
+            return None;
+        }
+        self.auction_equilibrium().map(|eq| eq.indicative())
+    }
+
+    /// Get the balances of an owner, if accounts are enabled and the owner has any
+    pub fn balance(&self, owner: OwnerId) -> Option<Account> {
+        self.accounts.as_ref().and_then(|accounts| accounts.get(owner))
+    }
+
+    /// Deposits funds into the account of an owner.
+    ///
+    /// # Parameters
+    /// - `owner`: The owner of the account
+    /// - `asset`: The [`Asset`] to deposit
+    /// - `amount`: The amount to deposit
+    ///
+    /// # Returns
+    /// The [`JournalLog`] of the operation if journaling is enabled.
+    ///
+    /// # Errors
+    /// Returns `Err` if accounts are not enabled.
+    pub fn deposit(
+        &mut self,
+        owner: OwnerId,
+        asset: Asset,
+        amount: u128,
+    ) -> Result<Option<JournalLog>> {
+        let Some(accounts) = self.accounts.as_mut() else {
+            return Err(make_error(ErrorType::AccountsDisabled));
+        };
+        accounts.deposit(owner, asset, amount);
+        Ok(self.journal(JournalOp::Deposit, OrderOptions::Deposit { owner, asset, amount }))
+    }
+
+    /// Withdraws funds from the free balance of an owner.
+    ///
+    /// # Parameters
+    /// - `owner`: The owner of the account
+    /// - `asset`: The [`Asset`] to withdraw
+    /// - `amount`: The amount to withdraw
+    ///
+    /// # Returns
+    /// The [`JournalLog`] of the operation if journaling is enabled.
+    ///
+    /// # Errors
+    /// Returns `Err` if accounts are not enabled or the free balance is insufficient.
+    pub fn withdraw(
+        &mut self,
+        owner: OwnerId,
+        asset: Asset,
+        amount: u128,
+    ) -> Result<Option<JournalLog>> {
+        let Some(accounts) = self.accounts.as_mut() else {
+            return Err(make_error(ErrorType::AccountsDisabled));
+        };
+        accounts.withdraw(owner, asset, amount)?;
+        Ok(self.journal(JournalOp::Withdraw, OrderOptions::Withdraw { owner, asset, amount }))
+    }
+
+    /// Get the position of an owner, if position tracking is enabled and the owner
+    /// has traded
+    pub fn position(&self, owner: OwnerId) -> Option<Position> {
+        self.positions.as_ref().and_then(|positions| positions.get(owner))
+    }
+
+    /// Get the unrealized PnL of an owner, marked at the given [`ReferencePrice`].
+    ///
+    /// Returns `None` if the owner has no position or the mark price is not available.
+    pub fn unrealized_pnl(&self, owner: OwnerId, mark: ReferencePrice) -> Option<i128> {
+        let position = self.position(owner)?;
+        let price = self.resolve_reference_price(mark)?;
+        Some(position.unrealized_pnl(price))
+    }
+
+    /// Get the cumulative traded notional of an owner, used to select its fee tier.
+    ///
+    /// Returns `None` if no fee schedule is configured.
+    pub fn traded_volume(&self, owner: OwnerId) -> Option<u128> {
+        self.fees.as_ref().map(|fees| fees.volume(Some(owner)))
+    }
+
+    /// Get the throttling counters of an owner.
+    ///
+    /// Returns `None` if throttling is disabled or the owner has not sent any message.
+    pub fn throttle_activity(&self, owner: OwnerId) -> Option<ThrottleActivity> {
+        self.throttle.as_ref().and_then(|t| t.activity.get(&owner).cloned())
+    }
+
     /// Get the spread (best ask - best bid)
     pub fn spread(&self) -> Option<Price> {
         match (self.best_bid(), self.best_ask()) {
@@ -470,6 +1200,15 @@ impl OrderBook {
     /// - `last_op`: the ID of the last operation performed
     /// - `next_order_id`: the next available order ID
     /// - `ts`: a timestamp representing when the snapshot was taken
+    /// - `last_trade_price` and `reference_price`: the prices used by the price band
+    /// - `accounts`: the balances of every owner, if accounts are enabled
+    /// - `positions`: the positions of every owner, if position tracking is enabled
+    /// - `fee_volumes`: the traded volume of every owner, if fees are enabled
+    /// - `throttle`: the throttling counters of every owner, if throttling is enabled
+    /// - `phase`: the current trading phase
+    /// - `next_trade_id` and `trades`: the next trade ID and the recent trades
+    /// - `candles`: the open candles, if candle aggregation is enabled
+    /// - `conflated_sequence`: the sequence number of the last conflated level-2 batch
     ///
     /// This function **does not fail** and can be called at any time.
     /// It returns a [`Snapshot`] struct, which can later be used with [`OrderBook::restore_snapshot`]
@@ -481,7 +1220,22 @@ impl OrderBook {
             asks: self.asks.clone(),
             last_op: self.last_op,
             next_order_id: self.next_order_id,
-            ts: current_timestamp_millis(),
+            ts: self.now(),
+            last_trade_price: self.last_trade_price,
+            reference_price: self.reference_price,
+            accounts: self.accounts.as_ref().map(|a| a.accounts.clone()),
+            positions: self.positions.as_ref().map(|p| p.positions.clone()),
+            fee_volumes: self.fees.as_ref().map(|f| f.volumes.clone()),
+            throttle: self.throttle.as_ref().map(|t| t.activity.clone()),
+            phase: self.phase,
+            auction_orders: self.auction_orders.clone(),
+            depth_sequence: self.depth_sequence,
+            l3_sequence: self.l3_sequence,
+            bbo_sequence: self.bbo_sequence,
+            next_trade_id: self.next_trade_id,
+            trades: self.trades.clone(),
+            candles: self.candles.as_ref().map(|c| c.open.clone()),
+            conflated_sequence: self.conflation.as_ref().map_or(0, |c| c.sequence),
         }
     }
 
@@ -490,6 +1244,9 @@ impl OrderBook {
     /// This replaces any existing orders and it is typically used when reconstructing
     /// an order book from persistent storage.
     ///
+    /// The state of an optional feature, such as the account balances or the
+    /// positions, is only restored when the feature is enabled on this book.
+    ///
     /// # Parameters
     /// - `snapshot`: The snapshot to load into the order book.
     pub fn restore_snapshot(&mut self, snapshot: Snapshot) {
@@ -498,6 +1255,42 @@ impl OrderBook {
         self.asks = snapshot.asks;
         self.last_op = snapshot.last_op;
         self.next_order_id = snapshot.next_order_id;
+        self.last_trade_price = snapshot.last_trade_price;
+        self.reference_price = snapshot.reference_price;
+        self.phase = snapshot.phase;
+        if let (Some(ledger), Some(accounts)) = (self.accounts.as_mut(), snapshot.accounts) {
+            ledger.accounts = accounts;
+        }
+        if let (Some(fees), Some(volumes)) = (self.fees.as_mut(), snapshot.fee_volumes) {
+            fees.volumes = volumes;
+        }
+        if let (Some(throttle), Some(activity)) = (self.throttle.as_mut(), snapshot.throttle) {
+            throttle.activity = activity;
+        }
+        if let (Some(tracker), Some(positions)) = (self.positions.as_mut(), snapshot.positions) {
+            tracker.positions = positions;
+        }
+        self.auction_orders = snapshot.auction_orders;
+        self.depth_sequence = snapshot.depth_sequence;
+        self.l3_sequence = snapshot.l3_sequence;
+        self.bbo_sequence = snapshot.bbo_sequence;
+        self.next_trade_id = snapshot.next_trade_id;
+        self.trades = snapshot.trades;
+        let excess = self.trades.len().saturating_sub(self.trade_history);
+        self.trades.drain(..excess);
+        if let (Some(candles), Some(open)) = (self.candles.as_mut(), snapshot.candles) {
+            candles.restore(open);
+        }
+        if let Some(conflation) = self.conflation.as_mut() {
+            conflation.sequence = snapshot.conflated_sequence;
+        }
+        self.last_bbo = self.top_of_book();
+        self.open_orders.clear();
+        for order in self.orders.values().chain(self.auction_orders.values()) {
+            if let Some(owner) = order.owner {
+                *self.open_orders.entry(owner).or_default() += 1;
+            }
+        }
     }
 
     /// Replays a sequence of journal logs to reconstruct the order book state.
@@ -518,14 +1311,52 @@ impl OrderBook {
         // sort logs by op_id ascending
         logs.sort_by_key(|log| log.op_id);
 
-        for log in &logs {
+        // The engine clock follows the journal during the replay
+        let clock = self.clock;
+        let result = self.apply_logs(&logs);
+        self.clock = clock;
+        result
+    }
+
+    fn apply_logs(&mut self, logs: &[JournalLog]) -> Result<()> {
+        for log in logs {
+            self.clock = Some(log.ts);
             match &log.o {
                 OrderOptions::Market(opts) => self.market(*opts)?,
                 OrderOptions::Limit(opts) => self.limit(*opts)?,
+                OrderOptions::Cancel(id) if log.op == JournalOp::SessionCancel => {
+                    self.remove_canceled(*id, JournalOp::SessionCancel)?;
+                    self.publish_market_data();
+                    continue;
+                }
                 OrderOptions::Cancel(id) => self.cancel(*id)?,
                 OrderOptions::Modify { id, price, quantity } => {
                     self.modify(*id, *price, *quantity)?
                 }
+                OrderOptions::SetReferencePrice(price) => {
+                    self.set_reference_price(*price)?;
+                    continue;
+                }
+                OrderOptions::Deposit { owner, asset, amount } => {
+                    self.deposit(*owner, *asset, *amount)?;
+                    continue;
+                }
+                OrderOptions::Withdraw { owner, asset, amount } => {
+                    self.withdraw(*owner, *asset, *amount)?;
+                    continue;
+                }
+                OrderOptions::SetPhase(phase) => {
+                    self.set_phase(*phase)?;
+                    continue;
+                }
+                OrderOptions::Uncross => {
+                    self.uncross()?;
+                    continue;
+                }
+                OrderOptions::MarketOnOpen(opts) => self.market_on_open(*opts)?,
+                OrderOptions::LimitOnOpen(opts) => self.limit_on_open(*opts)?,
+                OrderOptions::MarketOnClose(opts) => self.market_on_close(*opts)?,
+                OrderOptions::LimitOnClose(opts) => self.limit_on_close(*opts)?,
             };
         }
         Ok(())
@@ -537,56 +1368,124 @@ impl OrderBook {
     /// for both the bid and ask sides.
     ///
     /// # Parameters
-    /// - `limit`: Optional maximum number of price levels per side
+    /// - `limit`: Optional maximum number of price levels per side, every level if `None`
     ///
     /// # Returns
     /// A [`Depth`] struct containing the order book snapshot.
     pub fn depth(&self, limit: Option<usize>) -> Depth {
-        let levels = limit.unwrap_or(100);
+        let limit = limit.unwrap_or(usize::MAX);
         Depth {
-            asks: self.get_asks_prices_and_volume(levels),
-            bids: self.get_bids_prices_and_volume(levels),
+            asks: self.depth_levels(self.asks.iter(), limit, |price| price),
+            bids: self.depth_levels(self.bids.iter().rev(), limit, |price| price),
         }
     }
 
-    fn get_asks_prices_and_volume(&self, levels: usize) -> Vec<(Price, Quantity)> {
-        let mut asks = Vec::with_capacity(levels);
-        for (ask_price, queue) in self.asks.iter() {
-            let volume: Quantity = queue
-                .iter()
-                .filter_map(|id| self.orders.get(id))
-                .map(|order| order.remaining_qty())
-                .sum();
-            asks.push((*ask_price, volume));
+    /// Returns the depth of the order book with the price levels grouped into
+    /// buckets, like the price grouping of a trading UI.
+    ///
+    /// Bid prices are rounded down and ask prices up to a multiple of the
+    /// bucket size, so that a bucket never shows a better price than its
+    /// levels. Levels are only walked until `limit` buckets are filled.
+    ///
+    /// # Parameters
+    /// - `grouping`: The [`DepthGrouping`] of the buckets
+    /// - `limit`: Optional maximum number of buckets per side, every bucket if `None`
+    ///
+    /// # Errors
+    /// Returns `Err` if the bucket size is zero.
+    pub fn grouped_depth(&self, grouping: DepthGrouping, limit: Option<usize>) -> Result<Depth> {
+        if matches!(grouping, DepthGrouping::Ticks(0) | DepthGrouping::Step(Price(0))) {
+            return Err(make_error(ErrorType::InvalidDepthGrouping));
         }
-        asks
+        let default_spec = InstrumentSpec::default();
+        let spec = self.instrument.as_ref().unwrap_or(&default_spec);
+        let limit = limit.unwrap_or(usize::MAX);
+        Ok(Depth {
+            asks: self.depth_levels(self.asks.iter(), limit, |price| match grouping {
+                DepthGrouping::Ticks(n) => spec.round_up_ticks(price, n),
+                DepthGrouping::Step(step) => match price.value() % step.value() {
+                    0 => price,
+                    rem => Price(price.value().saturating_add(step.value() - rem)),
+                },
+            }),
+            bids: self.depth_levels(self.bids.iter().rev(), limit, |price| match grouping {
+                DepthGrouping::Ticks(n) => spec.round_down_ticks(price, n),
+                DepthGrouping::Step(step) => Price(price.value() - price.value() % step.value()),
+            }),
+        })
     }
 
-    fn get_bids_prices_and_volume(&self, levels: usize) -> Vec<(Price, Quantity)> {
-        let mut bids = Vec::with_capacity(levels);
-        for (bid_price, queue) in self.bids.iter().rev() {
-            let volume: Quantity = queue
+    /// Returns the current depth of the order book together with the sequence
+    /// number of the last level-2 update it includes.
+    ///
+    /// Used to bootstrap a consumer of the incremental level-2 feed, see
+    /// [`OrderBookEvent::DepthUpdate`].
+    ///
+    /// # Parameters
+    /// - `limit`: Optional maximum number of price levels per side
+    pub fn depth_snapshot(&self, limit: Option<usize>) -> DepthSnapshot {
+        DepthSnapshot { sequence: self.depth_sequence, depth: self.depth(limit) }
+    }
+
+    /// Aggregates the levels of a side, best level first, into the first
+    /// `limit` buckets of `bucket`. The notional uses the price of the levels.
+    fn depth_levels<'a>(
+        &self,
+        levels: impl Iterator<Item = (&'a Price, &'a VecDeque<OrderId>)>,
+        limit: usize,
+        bucket: impl Fn(Price) -> Price,
+    ) -> Vec<DepthLevel> {
+        let mut depth: Vec<DepthLevel> = Vec::new();
+        let mut cumulative_qty = Quantity(0);
+        let mut cumulative_notional = 0u128;
+        for (price, queue) in levels {
+            let bucket_price = bucket(*price);
+            let is_new = depth.last().is_none_or(|last| last.price != bucket_price);
+            if is_new && depth.len() == limit {
+                break;
+            }
+            let (quantity, order_count) = queue
                 .iter()
                 .filter_map(|id| self.orders.get(id))
-                .map(|order| order.remaining_qty())
-                .sum();
-            bids.push((*bid_price, volume));
+                .fold((Quantity(0), 0), |(qty, count), o| (qty.add(o.remaining_qty()), count + 1));
+            cumulative_qty = cumulative_qty.add(quantity);
+            cumulative_notional = cumulative_notional.saturating_add(notional(*price, quantity));
+            match depth.last_mut() {
+                Some(last) if !is_new => {
+                    last.quantity = last.quantity.add(quantity);
+                    last.order_count += order_count;
+                    last.cumulative_qty = cumulative_qty;
+                    last.cumulative_notional = cumulative_notional;
+                }
+                _ => depth.push(DepthLevel {
+                    price: bucket_price,
+                    quantity,
+                    order_count,
+                    cumulative_qty,
+                    cumulative_notional,
+                }),
+            }
         }
-        bids
+        depth
     }
 
+    /// Matches a buy order against the asks. Returns the unfilled quantity and
+    /// whether matching was stopped by a volatility interruption.
     fn match_with_asks(
         &mut self,
         quantity_to_fill: Quantity,
         fills: &mut Vec<FillReport>,
         limit_price: Option<Price>,
-    ) -> Quantity {
+    ) -> (Quantity, bool) {
         // Early exit if the side is empty
         if self.asks.is_empty() {
-            return quantity_to_fill;
+            return (quantity_to_fill, false);
         }
+        let threshold = self.volatility_range().map(|(_, upper)| upper);
+        let mut interrupted = false;
         let mut remaining_qty = quantity_to_fill;
         let mut filled_prices = Vec::new();
+        let mut matched_prices = Vec::new();
         for (ask_price, queue) in self.asks.iter_mut() {
             if remaining_qty.value() == 0 {
                 break;
@@ -596,29 +1495,43 @@ impl OrderBook {
                     break;
                 }
             }
+            if threshold.is_some_and(|upper| *ask_price > upper) {
+                interrupted = true;
+                break;
+            }
             remaining_qty = Self::process_queue(&mut self.orders, queue, remaining_qty, fills);
+            matched_prices.push(*ask_price);
             if queue.is_empty() {
                 filled_prices.push(*ask_price);
             }
         }
         for price in filled_prices {
             self.asks.remove(&price);
+            self.emit(OrderBookEvent::PriceLevelRemoved { side: Side::Sell, price });
         }
-        remaining_qty
+        for price in matched_prices {
+            self.emit_depth_update(Side::Sell, price);
+        }
+        (remaining_qty, interrupted)
     }
 
+    /// Matches a sell order against the bids. Returns the unfilled quantity and
+    /// whether matching was stopped by a volatility interruption.
     fn match_with_bids(
         &mut self,
         quantity_to_fill: Quantity,
         fills: &mut Vec<FillReport>,
         limit_price: Option<Price>,
-    ) -> Quantity {
+    ) -> (Quantity, bool) {
         // Early exit if the side is empty
         if self.bids.is_empty() {
-            return quantity_to_fill;
+            return (quantity_to_fill, false);
         }
+        let threshold = self.volatility_range().map(|(lower, _)| lower);
+        let mut interrupted = false;
         let mut remaining_qty = quantity_to_fill;
         let mut filled_prices = Vec::new();
+        let mut matched_prices = Vec::new();
         for (bid_price, queue) in self.bids.iter_mut().rev() {
             if remaining_qty.value() == 0 {
                 break;
@@ -628,15 +1541,24 @@ impl OrderBook {
                     break;
                 }
             }
+            if threshold.is_some_and(|lower| *bid_price < lower) {
+                interrupted = true;
+                break;
+            }
             remaining_qty = Self::process_queue(&mut self.orders, queue, remaining_qty, fills);
+            matched_prices.push(*bid_price);
             if queue.is_empty() {
                 filled_prices.push(*bid_price);
             }
         }
         for price in filled_prices {
             self.bids.remove(&price);
+            self.emit(OrderBookEvent::PriceLevelRemoved { side: Side::Buy, price });
         }
-        remaining_qty
+        for price in matched_prices {
+            self.emit_depth_update(Side::Buy, price);
+        }
+        (remaining_qty, interrupted)
     }
 
     fn process_queue(
@@ -658,31 +1580,787 @@ impl OrderBook {
                     price: head_order.price,
                     quantity: quantity_left,
                     status: head_order.status,
+                    owner: head_order.owner,
+                    maker_fee: 0,
+                    taker_fee: 0,
                 });
                 orders.insert(head_order.id, head_order);
 
                 quantity_left = Quantity(0);
             } else {
                 order_queue.pop_front();
-                quantity_left = quantity_left.sub(head_order.remaining_qty());
+                let fill_qty = head_order.remaining_qty();
+                quantity_left = quantity_left.sub(fill_qty);
 
-                head_order.executed_qty = head_order.executed_qty.add(head_order.remaining_qty());
+                head_order.executed_qty = head_order.executed_qty.add(fill_qty);
                 head_order.status = OrderStatus::Filled;
                 fills.push(FillReport {
                     order_id: head_order.id,
                     price: head_order.price,
-                    quantity: head_order.executed_qty,
+                    quantity: fill_qty,
                     status: head_order.status,
+                    owner: head_order.owner,
+                    maker_fee: 0,
+                    taker_fee: 0,
                 });
             }
         }
         quantity_left
     }
 
+    /// Charges the fees of every fill and updates the per-owner bookkeeping for
+    /// the taker and the maker orders involved in a match.
+    ///
+    /// `limit_price` is the taker limit price, or `None` for market orders, and
+    /// `quantity` is the taker quantity before matching.
+    ///
+    /// Returns the total fees paid by the taker.
+    fn settle_fills(
+        &mut self,
+        side: Side,
+        owner: Option<OwnerId>,
+        limit_price: Option<Price>,
+        quantity: Quantity,
+        fills: &mut [FillReport],
+    ) -> i128 {
+        if let Some(fill) = fills.last() {
+            self.last_trade_price = Some(fill.price);
+        }
+        let maker_side = match side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+        let mut taker_remaining = quantity;
+        let mut taker_fees = 0;
+        for fill in fills.iter_mut() {
+            if let Some(fees) = self.fees.as_mut() {
+                (fill.maker_fee, fill.taker_fee) =
+                    fees.charge(fill.owner, owner, notional(fill.price, fill.quantity));
+                taker_fees += fill.taker_fee;
+            }
+            if fill.status == OrderStatus::Filled {
+                self.untrack_open_order(fill.owner);
+            }
+            self.record_trade(fill.owner);
+            self.record_trade(owner);
+            if let Some(accounts) = self.accounts.as_mut() {
+                if let Some(maker) = fill.owner {
+                    // A partially filled maker is still in the book
+                    let maker_remaining = self
+                        .orders
+                        .get(&fill.order_id)
+                        .map_or(Quantity(0), |o| o.remaining_qty())
+                        .add(fill.quantity);
+                    accounts.settle(
+                        maker,
+                        maker_side,
+                        fill.price,
+                        fill.quantity,
+                        Some((fill.price, maker_remaining)),
+                        fill.maker_fee,
+                    );
+                }
+                if let Some(taker) = owner {
+                    accounts.settle(
+                        taker,
+                        side,
+                        fill.price,
+                        fill.quantity,
+                        limit_price.map(|price| (price, taker_remaining)),
+                        fill.taker_fee,
+                    );
+                }
+            }
+            if let Some(positions) = self.positions.as_mut() {
+                if let Some(maker) = fill.owner {
+                    positions.apply(maker, maker_side, fill.price, fill.quantity, fill.maker_fee);
+                }
+                if let Some(taker) = owner {
+                    positions.apply(taker, side, fill.price, fill.quantity, fill.taker_fee);
+                }
+            }
+            taker_remaining = taker_remaining.sub(fill.quantity);
+        }
+        taker_fees
+    }
+
+    /// Checks that the owner of a market order can pay for it.
+    ///
+    /// A market buy is checked against the notional of the levels it would sweep,
+    /// plus the fees at the highest rate of the fee schedule.
+    fn check_market_order_funds(&self, options: &MarketOrderOptions) -> Result<()> {
+        let Some(accounts) = self.accounts.as_ref() else { return Ok(()) };
+        let Some(owner) = options.owner else {
+            return Err(make_error(ErrorType::OwnerRequired));
+        };
+        match options.side {
+            Side::Buy => accounts.ensure_free(
+                owner,
+                Asset::Quote,
+                self.sweep_cost(options.side, options.quantity, accounts.fee_buffer_bps),
+            ),
+            Side::Sell => {
+                accounts.ensure_free(owner, Asset::Base, u128::from(options.quantity.value()))
+            }
+        }
+    }
+
+    fn reserve_limit_order_funds(&mut self, options: &LimitOrderOptions) -> Result<()> {
+        let Some(accounts) = self.accounts.as_mut() else { return Ok(()) };
+        let Some(owner) = options.owner else {
+            return Err(make_error(ErrorType::OwnerRequired));
+        };
+        accounts.reserve(owner, options.side, options.price, options.quantity)
+    }
+
+    /// Looks up a resting order or a pending auction-only order.
+    fn find_order(&self, id: OrderId) -> Option<&LimitOrder> {
+        self.orders.get(&id).or_else(|| self.auction_orders.get(&id))
+    }
+
+    /// Removes a resting order from the book, or a pending auction-only order,
+    /// and releases the funds reserved for its unfilled part.
+    fn remove_order(&mut self, id: OrderId) -> Option<LimitOrder> {
+        let order = match self.orders.remove(&id) {
+            Some(order) => {
+                let book_side = match order.side {
+                    Side::Buy => &mut self.bids,
+                    Side::Sell => &mut self.asks,
+                };
+                // Auction-only market orders are matched without ever resting
+                // in a price level, so they have no market data to update
+                let queue = book_side.get_mut(&order.price);
+                let position = queue.as_ref().and_then(|q| q.iter().position(|x| *x == id));
+                if let (Some(queue), Some(pos)) = (queue, position) {
+                    queue.remove(pos);
+                    if queue.is_empty() {
+                        book_side.remove(&order.price);
+                        self.emit(OrderBookEvent::PriceLevelRemoved {
+                            side: order.side,
+                            price: order.price,
+                        });
+                    }
+                    self.emit_depth_update(order.side, order.price);
+                    // Replaced orders are reported once the new order rests
+                    if order.remaining_qty().value() > 0 && self.replacing != Some(id) {
+                        self.emit_l3(L3Message::Delete {
+                            id,
+                            side: order.side,
+                            price: order.price,
+                            quantity: order.remaining_qty(),
+                        });
+                    }
+                }
+                order
+            }
+            None => self.auction_orders.remove(&id)?,
+        };
+        self.untrack_open_order(order.owner);
+        self.release_order_funds(&order);
+        Some(order)
+    }
+
+    /// Appends an order to the queue of its price level, creating the level if needed.
+    fn push_to_level(&mut self, side: Side, price: Price, id: OrderId) {
+        let book_side = match side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        };
+        let queue = book_side.entry(price).or_default();
+        queue.push_back(id);
+        if queue.len() == 1 {
+            self.emit(OrderBookEvent::PriceLevelAdded { side, price });
+        }
+        self.emit_depth_update(side, price);
+
+        let order = self.orders[&id];
+        let quantity = order.remaining_qty();
+        let message = match self.replacing.take() {
+            Some(old) => L3Message::Replace { id: old, new_id: id, side, price, quantity },
+            None => {
+                let owner = order.owner.filter(|_| self.order_attribution);
+                L3Message::Add { id, side, price, quantity, owner }
+            }
+        };
+        self.emit_l3(message);
+    }
+
+    /// Publishes a message on the market-by-order feed.
+    fn emit_l3(&mut self, message: L3Message) {
+        self.l3_sequence = safe_add(self.l3_sequence, 1);
+        if self.listeners.is_empty() {
+            return;
+        }
+        let update = L3Update { sequence: self.l3_sequence, message };
+        self.emit(OrderBookEvent::L3Update(update));
+    }
+
+    /// Total remaining quantity of a price level, zero if the level does not exist.
+    fn level_qty(&self, side: Side, price: Price) -> Quantity {
+        let book_side = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        book_side.get(&price).map_or(Quantity(0), |queue| {
+            queue.iter().filter_map(|id| self.orders.get(id)).map(|o| o.remaining_qty()).sum()
+        })
+    }
+
+    /// Publishes the new total quantity of a price level on the level-2 feed.
+    fn emit_depth_update(&mut self, side: Side, price: Price) {
+        self.depth_sequence = safe_add(self.depth_sequence, 1);
+        if self.listeners.is_empty() {
+            return;
+        }
+        let update = DepthUpdate {
+            sequence: self.depth_sequence,
+            side,
+            price,
+            quantity: self.level_qty(side, price),
+        };
+        let now = self.now();
+        if let Some(conflation) = self.conflation.as_mut() {
+            conflation.record(update, now);
+        }
+        self.emit(OrderBookEvent::DepthUpdate(update));
+    }
+
+    /// Publishes the market data derived from the state of the book at the end
+    /// of an operation.
+    fn publish_market_data(&mut self) {
+        self.publish_bbo();
+        self.publish_conflated(false);
+    }
+
+    /// Publishes the pending conflated level-2 updates if they are due, or
+    /// whenever some are pending with `force`.
+    fn publish_conflated(&mut self, force: bool) {
+        let Some(conflation) = self.conflation.as_ref() else { return };
+        let now = self.now();
+        if !force && !conflation.is_due(now) {
+            return;
+        }
+        if let Some(batch) = self.conflation.as_mut().and_then(|c| c.flush(now)) {
+            self.emit(OrderBookEvent::ConflatedDepth(batch));
+        }
+    }
+
+    /// Publishes the top of the book if it changed since the last publication.
+    ///
+    /// Listeners are only attached when the book is built, so a book without
+    /// listeners never computes its top of book.
+    fn publish_bbo(&mut self) {
+        if self.listeners.is_empty() {
+            return;
+        }
+        let current = self.top_of_book();
+        if current == self.last_bbo {
+            return;
+        }
+        let previous = std::mem::replace(&mut self.last_bbo, current);
+        self.bbo_sequence = safe_add(self.bbo_sequence, 1);
+        self.emit(OrderBookEvent::BboUpdate(BboUpdate {
+            sequence: self.bbo_sequence,
+            previous,
+            current,
+        }));
+    }
+
+    /// Adds a trade to the recent trades and publishes it.
+    fn push_trade(
+        &mut self,
+        maker_order_id: OrderId,
+        taker_order_id: OrderId,
+        aggressor: Option<Side>,
+        price: Price,
+        quantity: Quantity,
+    ) {
+        let trade = Trade {
+            id: self.next_trade_id,
+            maker_order_id,
+            taker_order_id,
+            aggressor,
+            price,
+            quantity,
+            ts: self.now(),
+        };
+        self.next_trade_id += 1;
+        if self.trade_history > 0 {
+            if self.trades.len() == self.trade_history {
+                self.trades.pop_front();
+            }
+            self.trades.push_back(trade);
+        }
+        let closed = self.candles.as_mut().map_or_else(Vec::new, |c| c.apply(&trade));
+        for candle in closed {
+            self.emit(OrderBookEvent::CandleClosed(candle));
+        }
+        self.emit(OrderBookEvent::Trade(trade));
+    }
+
+    /// Dispatches an event to every registered listener.
+    fn emit(&mut self, event: OrderBookEvent) {
+        for listener in self.listeners.iter_mut() {
+            listener.on_event(&event);
+        }
+    }
+
+    /// Emits the fill events of the makers and of the taker of a match, and
+    /// records a trade for every fill.
+    fn emit_fills(&mut self, taker: OrderId, side: Side, quantity: Quantity, fills: &[FillReport]) {
+        let maker_side = match side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+        let mut taker_remaining = quantity;
+        for fill in fills {
+            let maker_remaining =
+                self.orders.get(&fill.order_id).map_or(Quantity(0), |o| o.remaining_qty());
+            self.emit_l3(L3Message::Execute {
+                id: fill.order_id,
+                side: maker_side,
+                price: fill.price,
+                quantity: fill.quantity,
+            });
+            self.emit(OrderBookEvent::fill(
+                fill.order_id,
+                maker_side,
+                fill.price,
+                fill.quantity,
+                maker_remaining,
+            ));
+            taker_remaining = taker_remaining.sub(fill.quantity);
+            self.emit(OrderBookEvent::fill(
+                taker,
+                side,
+                fill.price,
+                fill.quantity,
+                taker_remaining,
+            ));
+            self.push_trade(fill.order_id, taker, Some(side), fill.price, fill.quantity);
+        }
+    }
+
+    /// Emits the rejection of a new order.
+    fn emit_rejection(
+        &mut self,
+        order_type: OrderType,
+        side: Side,
+        owner: Option<OwnerId>,
+        result: &Result<ExecutionReport>,
+    ) {
+        if let Err(error) = result {
+            let error = error.clone();
+            self.emit(OrderBookEvent::OrderRejected { order_type, side, owner, error });
+        }
+    }
+
+    /// Releases the funds still reserved for the unfilled part of an order.
+    fn release_order_funds(&mut self, order: &LimitOrder) {
+        if let (Some(accounts), Some(owner)) = (self.accounts.as_mut(), order.owner) {
+            accounts.release(owner, order.side, order.price, order.remaining_qty());
+        }
+    }
+
+    /// Records a non-order operation in the journal, if journaling is enabled.
+    fn journal(&mut self, op: JournalOp, o: OrderOptions) -> Option<JournalLog> {
+        if !self.journaling {
+            return None;
+        }
+        self.last_op = safe_add(self.last_op, 1);
+        Some(JournalLog { op_id: self.last_op, ts: self.now(), op, o })
+    }
+
+    /// Computes the equilibrium of the book at its current state, including the
+    /// auction-only orders that take part in the current auction.
+    fn auction_equilibrium(&self) -> Option<Equilibrium> {
+        let depth = self.depth(None);
+        let mut bids: BTreeMap<Price, Quantity> =
+            depth.bids.iter().map(|level| (level.price, level.quantity)).collect();
+        let mut asks: BTreeMap<Price, Quantity> =
+            depth.asks.iter().map(|level| (level.price, level.quantity)).collect();
+        let mut market_buy = Quantity(0);
+        let mut market_sell = Quantity(0);
+        for order in self
+            .auction_orders
+            .values()
+            .filter(|o| o.order_type.auction_phase() == Some(self.phase))
+        {
+            let quantity = order.remaining_qty();
+            let total = match (order.order_type.is_market(), order.side) {
+                (true, Side::Buy) => &mut market_buy,
+                (true, Side::Sell) => &mut market_sell,
+                (false, Side::Buy) => bids.entry(order.price).or_insert(Quantity(0)),
+                (false, Side::Sell) => asks.entry(order.price).or_insert(Quantity(0)),
+            };
+            *total = total.add(quantity);
+        }
+        equilibrium(
+            &bids.into_iter().rev().collect::<Vec<_>>(),
+            &asks.into_iter().collect::<Vec<_>>(),
+            market_buy,
+            market_sell,
+            self.reference_price.or(self.last_trade_price),
+        )
+    }
+
+    /// Accepts an auction-only order, which stays outside the book until its
+    /// auction is uncrossed. Market orders are carried with a zero price.
+    fn auction_only_order(
+        &mut self,
+        op: JournalOp,
+        order_type: OrderType,
+        options: LimitOrderOptions,
+        o: OrderOptions,
+    ) -> Result<ExecutionReport> {
+        let report = self.submit_auction_only(op, order_type, options, o);
+        self.emit_rejection(order_type, options.side, options.owner, &report);
+        report
+    }
+
+    fn submit_auction_only(
+        &mut self,
+        op: JournalOp,
+        order_type: OrderType,
+        options: LimitOrderOptions,
+        o: OrderOptions,
+    ) -> Result<ExecutionReport> {
+        self.check_phase(op)?;
+        self.check_throttle(options.owner, true)?;
+        if options.quantity.value() == 0 {
+            return Err(make_error(ErrorType::InvalidQuantity));
+        }
+        let is_market = order_type.is_market();
+        if is_market {
+            self.check_market_order_risk(&MarketOrderOptions {
+                side: options.side,
+                quantity: options.quantity,
+                owner: options.owner,
+            })?;
+            if let Some(spec) = &self.instrument {
+                spec.validate_quantity(options.quantity)?;
+            }
+            // The price of a market buy is unknown until the auction
+            if options.side == Side::Buy && self.accounts.is_some() {
+                return Err(make_error(ErrorType::AuctionMarketBuyUnfunded));
+            }
+        } else {
+            if options.price.value() == 0 {
+                return Err(make_error(ErrorType::InvalidPrice));
+            }
+            self.check_limit_order_risk(&options)?;
+            if let Some(spec) = &self.instrument {
+                spec.validate_limit(options.price, options.quantity)?;
+            }
+        }
+        self.reserve_limit_order_funds(&options)?;
+
+        self.record_message(options.owner, true);
+        let mut order = LimitOrder::new(self.new_order_id(), options, self.now());
+        order.order_type = order_type;
+        self.emit(OrderBookEvent::OrderAccepted {
+            id: order.id,
+            order_type,
+            side: order.side,
+            price: (!is_market).then_some(order.price),
+            quantity: order.orig_qty,
+            owner: order.owner,
+        });
+        self.auction_orders.insert(order.id, order);
+        self.track_open_order(order.owner);
+
+        let mut report = ExecutionReport::new(ExecutionReportParams {
+            id: order.id,
+            order_type,
+            side: order.side,
+            quantity: order.orig_qty,
+            status: order.status,
+            time_in_force: None,
+            price: (!is_market).then_some(order.price),
+            post_only: false,
+        });
+        report.log = self.journal(op, o);
+        Ok(report)
+    }
+
+    /// Executes `quantity` of a resting order at the auction `price`, and
+    /// updates the per-owner bookkeeping of its owner.
+    fn execute_auction_fill(
+        &mut self,
+        id: OrderId,
+        price: Price,
+        quantity: Quantity,
+    ) -> FillReport {
+        let mut order = self.orders[&id];
+        let remaining = order.remaining_qty();
+        order.executed_qty = order.executed_qty.add(quantity);
+        order.status =
+            if quantity < remaining { OrderStatus::PartiallyFilled } else { OrderStatus::Filled };
+        self.orders.insert(id, order);
+        if !order.order_type.is_market() {
+            self.emit_l3(L3Message::Execute { id, side: order.side, price, quantity });
+        }
+        self.emit(OrderBookEvent::fill(id, order.side, price, quantity, order.remaining_qty()));
+
+        if order.status == OrderStatus::Filled {
+            self.remove_order(id);
+        } else if !order.order_type.is_market() {
+            self.emit_depth_update(order.side, order.price);
+        }
+
+        let fee = self
+            .fees
+            .as_mut()
+            .map_or(0, |fees| fees.charge_maker(order.owner, notional(price, quantity)));
+        if let Some(owner) = order.owner {
+            if let Some(accounts) = self.accounts.as_mut() {
+                accounts.settle(
+                    owner,
+                    order.side,
+                    price,
+                    quantity,
+                    Some((order.price, remaining)),
+                    fee,
+                );
+            }
+            if let Some(positions) = self.positions.as_mut() {
+                positions.apply(owner, order.side, price, quantity, fee);
+            }
+        }
+        self.record_trade(order.owner);
+
+        FillReport {
+            order_id: id,
+            price,
+            quantity,
+            status: order.status,
+            owner: order.owner,
+            maker_fee: fee,
+            taker_fee: 0,
+        }
+    }
+
+    /// Checks that the current trading phase accepts the operation.
+    fn check_phase(&self, op: JournalOp) -> Result<()> {
+        match (self.phase, op) {
+            (TradingPhase::Closed, _) => Err(make_error(ErrorType::MarketClosed)),
+            (TradingPhase::Halted, JournalOp::Cancel) => Ok(()),
+            (TradingPhase::Halted, _) => Err(make_error(ErrorType::TradingHalted)),
+            (phase, JournalOp::Market) if phase.is_auction() => {
+                Err(make_error(ErrorType::OrderNotAllowedInPhase))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the throttling limits of the owner of a message.
+    fn check_throttle(&self, owner: Option<OwnerId>, is_order: bool) -> Result<()> {
+        match (self.throttle.as_ref(), owner) {
+            (Some(throttle), Some(owner)) => throttle.check(owner, self.now(), is_order),
+            _ => Ok(()),
+        }
+    }
+
+    fn record_message(&mut self, owner: Option<OwnerId>, is_order: bool) {
+        let now = self.now();
+        if let (Some(throttle), Some(owner)) = (self.throttle.as_mut(), owner) {
+            throttle.record(owner, now, is_order);
+        }
+    }
+
+    fn record_trade(&mut self, owner: Option<OwnerId>) {
+        if let (Some(throttle), Some(owner)) = (self.throttle.as_mut(), owner) {
+            throttle.record_trade(owner);
+        }
+    }
+
+    fn track_open_order(&mut self, owner: Option<OwnerId>) {
+        if let Some(owner) = owner {
+            *self.open_orders.entry(owner).or_default() += 1;
+        }
+    }
+
+    fn untrack_open_order(&mut self, owner: Option<OwnerId>) {
+        if let Some(owner) = owner {
+            if let Some(count) = self.open_orders.get_mut(&owner) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    self.open_orders.remove(&owner);
+                }
+            }
+        }
+    }
+
+    /// Checks the replacement of a modified order against the instrument
+    /// specification, the risk limits, the price band and the free balance.
+    fn check_replacement(
+        &self,
+        order: &LimitOrder,
+        price: Option<Price>,
+        quantity: Option<Quantity>,
+    ) -> Result<()> {
+        if price.is_none() && quantity.is_none() {
+            return Ok(());
+        }
+        let new_price = price.unwrap_or(order.price);
+        let new_qty = quantity.unwrap_or(order.remaining_qty());
+        if let Some(spec) = &self.instrument {
+            spec.validate_limit(new_price, new_qty)?;
+        }
+        self.check_limit_order_size(new_price, new_qty)?;
+        let options =
+            LimitOrderOptions::new(order.side, new_qty.value(), new_price.value(), None, None);
+        let options = self.apply_price_band(options)?;
+        if let (Some(accounts), Some(owner)) = (self.accounts.as_ref(), order.owner) {
+            accounts.ensure_replaceable(
+                owner,
+                order.side,
+                (order.price, order.remaining_qty()),
+                (options.price, options.quantity),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Checks the size limits of a limit order, which a modify also has to
+    /// meet before the original order is canceled.
+    fn check_limit_order_size(&self, price: Price, quantity: Quantity) -> Result<()> {
+        let Some(limits) = self.risk_limits else { return Ok(()) };
+        if let Some(max_qty) = limits.max_order_qty {
+            if quantity > max_qty {
+                return Err(make_error(ErrorType::RiskMaxOrderQty));
+            }
+        }
+        if let Some(max_notional) = limits.max_notional {
+            if notional(price, quantity) > max_notional {
+                return Err(make_error(ErrorType::RiskMaxNotional));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_market_order_risk(&self, options: &MarketOrderOptions) -> Result<()> {
+        let Some(limits) = self.risk_limits else { return Ok(()) };
+        if let Some(max_qty) = limits.max_order_qty {
+            if options.quantity > max_qty {
+                return Err(make_error(ErrorType::RiskMaxOrderQty));
+            }
+        }
+        if let Some(max_notional) = limits.max_notional {
+            // A market order has no price, so the notional is estimated by
+            // walking the levels the order would sweep.
+            if self.market_order_notional(options.side, options.quantity) > max_notional {
+                return Err(make_error(ErrorType::RiskMaxNotional));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_limit_order_risk(&self, options: &LimitOrderOptions) -> Result<()> {
+        self.check_limit_order_size(options.price, options.quantity)?;
+        let Some(limits) = self.risk_limits else { return Ok(()) };
+        if let (Some(max_orders), Some(owner)) = (limits.max_open_orders_per_owner, options.owner) {
+            let time_in_force = options.time_in_force.unwrap_or(TimeInForce::GTC);
+            let open_orders = self.open_orders.get(&owner).copied().unwrap_or(0);
+            if time_in_force == TimeInForce::GTC && open_orders >= max_orders {
+                return Err(make_error(ErrorType::RiskMaxOpenOrders));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the `(lower, upper)` edges of the configured price band, or `None`
+    /// if no band is configured or its reference price is not available.
+    fn price_band_edges(&self) -> Option<(Price, Price)> {
+        let band = self.price_band?;
+        let reference = self.resolve_reference_price(band.reference)?;
+        Some(band.edges(reference))
+    }
+
+    /// Returns the `(lower, upper)` prices trades can execute at before a
+    /// volatility interruption is triggered.
+    fn volatility_range(&self) -> Option<(Price, Price)> {
+        self.volatility_interruption?.range(self.reference_price, self.last_trade_price)
+    }
+
+    /// Switches the book to the phase of the configured volatility interruption.
+    fn interrupt(&mut self) {
+        if let Some(vi) = self.volatility_interruption {
+            self.phase = match vi.phase {
+                InterruptionPhase::Halted => TradingPhase::Halted,
+                InterruptionPhase::Auction => TradingPhase::Auction,
+            };
+        }
+    }
+
+    fn resolve_reference_price(&self, reference: ReferencePrice) -> Option<Price> {
+        match reference {
+            ReferencePrice::LastTrade => self.last_trade_price,
+            ReferencePrice::Mid => self.mid_price(),
+            ReferencePrice::External => self.reference_price,
+        }
+    }
+
+    /// Checks a limit order against the price band, capping its price to the band
+    /// edge when the band is configured with [`PriceBandAction::Cap`].
+    fn apply_price_band(&self, mut options: LimitOrderOptions) -> Result<LimitOrderOptions> {
+        let (Some(band), Some((lower, upper))) = (self.price_band, self.price_band_edges()) else {
+            return Ok(options);
+        };
+        // Capped prices stay inside the band and on the tick size
+        let spec = self.instrument.as_ref();
+        let edge = match options.side {
+            Side::Buy if options.price > upper => spec.map_or(upper, |s| s.round_down(upper)),
+            Side::Sell if options.price < lower => spec.map_or(lower, |s| s.round_up(lower)),
+            _ => return Ok(options),
+        };
+        match band.action {
+            PriceBandAction::Reject => Err(make_error(ErrorType::OrderOutsidePriceBand)),
+            PriceBandAction::Cap => {
+                options.price = edge;
+                Ok(options)
+            }
+        }
+    }
+
+    fn market_order_notional(&self, side: Side, quantity: Quantity) -> u128 {
+        self.sweep_cost(side, quantity, 0)
+    }
+
+    /// Walks the levels a market order of `quantity` would sweep and returns the
+    /// notional of every fill, plus the fee on each fill at `fee_bps`.
+    fn sweep_cost(&self, side: Side, quantity: Quantity, fee_bps: u64) -> u128 {
+        let levels: Box<dyn Iterator<Item = (&Price, &VecDeque<OrderId>)>> = match side {
+            Side::Buy => Box::new(self.asks.iter()),
+            Side::Sell => Box::new(self.bids.iter().rev()),
+        };
+        let mut remaining = quantity;
+        let mut total = 0u128;
+        for (price, queue) in levels {
+            for order in queue.iter().filter_map(|id| self.orders.get(id)) {
+                if remaining.value() == 0 {
+                    return total;
+                }
+                let qty = if order.remaining_qty() < remaining {
+                    order.remaining_qty()
+                } else {
+                    remaining
+                };
+                let value = notional(*price, qty);
+                total += value + fee(value, fee_bps as i64) as u128;
+                remaining = remaining.sub(qty);
+            }
+        }
+        total
+    }
+
     fn validate_market_order(&self, options: &MarketOrderOptions) -> Result<()> {
         if options.quantity.value() == 0 {
             return Err(make_error(ErrorType::InvalidQuantity));
         }
+        if let Some(spec) = &self.instrument {
+            spec.validate_quantity(options.quantity)?;
+        }
         if (options.side == Side::Buy && self.asks.is_empty())
             || (options.side == Side::Sell && self.bids.is_empty())
         {
@@ -698,6 +2376,9 @@ impl OrderBook {
         if options.price.value() == 0 {
             return Err(make_error(ErrorType::InvalidPrice));
         }
+        if let Some(spec) = &self.instrument {
+            spec.validate_limit(options.price, options.quantity)?;
+        }
         let time_in_force = options.time_in_force.unwrap_or(TimeInForce::GTC);
         if time_in_force == TimeInForce::FOK
             && !self.limit_order_is_fillable(options.side, options.quantity, options.price)
@@ -729,10 +2410,15 @@ impl OrderBook {
         Ok(())
     }
 
+    /// Only the liquidity within the volatility range counts, as matching
+    /// beyond it would interrupt the order.
     fn limit_order_is_fillable(&self, side: Side, quantity: Quantity, price: Price) -> bool {
+        let range = self.volatility_range();
         if side == Side::Buy {
+            let price = range.map_or(price, |(_, upper)| price.min(upper));
             self.limit_buy_order_is_fillable(quantity, price)
         } else {
+            let price = range.map_or(price, |(lower, _)| price.max(lower));
             self.limit_sell_order_is_fillable(quantity, price)
         }
     }
@@ -806,5 +2492,20 @@ impl fmt::Display for OrderBook {
     }
 }
 
+/// Converts the options of a market auction-only order into limit options
+/// with a zero price, the actual price being set by the auction.
+fn market_auction_options(options: &MarketOrderOptions) -> LimitOrderOptions {
+    LimitOrderOptions {
+        side: options.side,
+        quantity: options.quantity,
+        price: Price(0),
+        time_in_force: None,
+        post_only: None,
+        owner: options.owner,
+        session: None,
+        persist: false,
+    }
+}
+
 #[cfg(test)]
 mod tests;
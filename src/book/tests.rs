@@ -1,5 +1,15 @@
 use super::*;
-use crate::{OrderBook, OrderBookBuilder};
+use crate::{
+    Balance, BboUpdate, Candle, CandleInterval, ConflatedDepthUpdate, ConflationPolicy,
+    DepthGrouping, DepthLevel, FeeSchedule, InstrumentSpec, InterruptionPhase,
+    InterruptionRemainder, L3Message, L3Update, OrderBook, OrderBookBuilder, OrderBookEvent,
+    OrderBookListener, ThrottleLimits, TickTable, TopOfBook, Trade, TradeId,
+    VolatilityInterruption,
+};
+
+fn levels(levels: &[DepthLevel]) -> Vec<(Price, Quantity)> {
+    levels.iter().map(|level| (level.price, level.quantity)).collect()
+}
 
 fn make_order_book(options: Option<OrderBookOptions>) -> OrderBook {
     OrderBookBuilder::new("BTC-USD").with_options(options.unwrap_or_default()).build()
@@ -11,8 +21,16 @@ fn get_populated_order_book(
 ) -> OrderBook {
     let mut ob = make_order_book(options);
     for (side, quantity, price) in limit_orders {
-        let order =
-            LimitOrderOptions { side, quantity, price, time_in_force: None, post_only: None };
+        let order = LimitOrderOptions {
+            side,
+            quantity,
+            price,
+            time_in_force: None,
+            post_only: None,
+            owner: None,
+            session: None,
+            persist: false,
+        };
         let _ = ob.limit(order);
     }
     ob
@@ -27,19 +45,19 @@ fn test_market_order() {
             (Side::Sell, Quantity(3), Price(1001)),
             (Side::Sell, Quantity(5), Price(1002)),
         ],
-        Some(OrderBookOptions { journaling: true, snapshot: None, replay_logs: None }),
+        Some(OrderBookOptions { journaling: true, ..Default::default() }),
     );
     // Testing raw constructor
     let m1 = MarketOrderOptions::new(Side::Buy, 4);
-    let m2 = MarketOrderOptions { side: Side::Sell, quantity: Quantity(4) };
+    let m2 = MarketOrderOptions { side: Side::Sell, quantity: Quantity(4), owner: None };
     // this order should fill the entire order side
-    let m3 = MarketOrderOptions { side: Side::Sell, quantity: Quantity(10) };
+    let m3 = MarketOrderOptions { side: Side::Sell, quantity: Quantity(10), owner: None };
 
     let resp = ob.market(m1);
     let resp = resp.unwrap();
     let depth = ob.depth(Some(10));
-    assert_eq!(depth.asks, vec![(Price(1002), Quantity(4))]);
-    assert_eq!(depth.bids, vec![(Price(999), Quantity(3)), (Price(998), Quantity(5))]);
+    assert_eq!(levels(&depth.asks), vec![(Price(1002), Quantity(4))]);
+    assert_eq!(levels(&depth.bids), vec![(Price(999), Quantity(3)), (Price(998), Quantity(5))]);
     assert_eq!(resp.orig_qty, m1.quantity);
     assert_eq!(resp.executed_qty, m1.quantity);
     assert_eq!(resp.remaining_qty, Quantity(0));
@@ -53,8 +71,8 @@ fn test_market_order() {
     // Test market_raw
     let resp = ob.market_raw(m2.side, m2.quantity.value());
     let resp = resp.unwrap();
-    assert_eq!(ob.depth(Some(10)).asks, vec!((Price(1002), Quantity(4))));
-    assert_eq!(ob.depth(Some(10)).bids, vec!((Price(998), Quantity(4))));
+    assert_eq!(levels(&ob.depth(Some(10)).asks), vec!((Price(1002), Quantity(4))));
+    assert_eq!(levels(&ob.depth(Some(10)).bids), vec!((Price(998), Quantity(4))));
     assert_eq!(resp.orig_qty, m2.quantity);
     assert_eq!(resp.executed_qty, m2.quantity);
     assert_eq!(resp.remaining_qty, Quantity(0));
@@ -79,12 +97,12 @@ fn test_market_order_errors() {
     let mut ob = get_populated_order_book(vec![(Side::Buy, Quantity(5), Price(1000))], None);
 
     // invalid quantity
-    let m1 = MarketOrderOptions { side: Side::Buy, quantity: Quantity(0) };
+    let m1 = MarketOrderOptions { side: Side::Buy, quantity: Quantity(0), owner: None };
     let resp = ob.market(m1);
     assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::InvalidQuantity).code));
 
     // side empty
-    let m2 = MarketOrderOptions { side: Side::Buy, quantity: Quantity(10) };
+    let m2 = MarketOrderOptions { side: Side::Buy, quantity: Quantity(10), owner: None };
     let resp = ob.market(m2);
     assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderBookEmpty).code));
 }
@@ -100,11 +118,14 @@ fn test_limit_order() {
         price: Price(1100),
         time_in_force: None,
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
 
     let _ = ob.limit(l1);
-    assert_eq!(ob.depth(Some(10)).bids, vec!((Price(1000), Quantity(5))));
-    assert_eq!(ob.depth(Some(10)).asks, vec!());
+    assert_eq!(levels(&ob.depth(Some(10)).bids), vec!((Price(1000), Quantity(5))));
+    assert_eq!(levels(&ob.depth(Some(10)).asks), vec!());
 
     // Test limit raw
     let _ = ob.limit_raw(
@@ -114,8 +135,8 @@ fn test_limit_order() {
         l2.time_in_force,
         l2.post_only,
     );
-    assert_eq!(ob.depth(Some(10)).bids, vec!((Price(1000), Quantity(5))));
-    assert_eq!(ob.depth(Some(10)).asks, vec!((Price(1100), Quantity(5))));
+    assert_eq!(levels(&ob.depth(Some(10)).bids), vec!((Price(1000), Quantity(5))));
+    assert_eq!(levels(&ob.depth(Some(10)).asks), vec!((Price(1100), Quantity(5))));
 
     // immediate matching limit order
     let l3 = LimitOrderOptions {
@@ -124,6 +145,9 @@ fn test_limit_order() {
         price: Price(1100),
         time_in_force: None,
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l3);
     let resp = resp.unwrap();
@@ -140,6 +164,9 @@ fn test_limit_order() {
         price: Price(1100),
         time_in_force: None,
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l4);
     let resp = resp.unwrap();
@@ -157,6 +184,9 @@ fn test_limit_order() {
         price: Price(1100),
         time_in_force: Some(TimeInForce::FOK),
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l5);
     let resp = resp.unwrap();
@@ -173,6 +203,9 @@ fn test_limit_order() {
         price: Price(1100),
         time_in_force: Some(TimeInForce::IOC),
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l6);
     let resp = resp.unwrap();
@@ -187,10 +220,10 @@ fn test_limit_order() {
 fn test_order_book_options() {
     let mut ob = get_populated_order_book(
         vec![(Side::Sell, Quantity(5), Price(1100))],
-        Some(OrderBookOptions { journaling: true, snapshot: None, replay_logs: None }),
+        Some(OrderBookOptions { journaling: true, ..Default::default() }),
     );
 
-    let l1 = MarketOrderOptions { side: Side::Buy, quantity: Quantity(5) };
+    let l1 = MarketOrderOptions { side: Side::Buy, quantity: Quantity(5), owner: None };
     let resp = ob.market(l1);
     let resp = resp.unwrap();
     assert!(resp.log.is_some());
@@ -202,6 +235,9 @@ fn test_order_book_options() {
         price: Price(1000),
         time_in_force: None,
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l2);
     let resp = resp.unwrap();
@@ -209,7 +245,7 @@ fn test_order_book_options() {
     assert_eq!(resp.log.unwrap().op, JournalOp::Limit);
 
     let mut ob = get_populated_order_book(vec![(Side::Sell, Quantity(5), Price(1100))], None);
-    let l1 = MarketOrderOptions { side: Side::Buy, quantity: Quantity(5) };
+    let l1 = MarketOrderOptions { side: Side::Buy, quantity: Quantity(5), owner: None };
     let resp = ob.market(l1);
     let resp = resp.unwrap();
     assert!(resp.log.is_none());
@@ -220,6 +256,9 @@ fn test_order_book_options() {
         price: Price(1000),
         time_in_force: None,
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l2);
     let resp = resp.unwrap();
@@ -247,6 +286,9 @@ fn test_limit_order_errors() {
         price: Price(1000),
         time_in_force: None,
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l1);
     assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::InvalidQuantity).code));
@@ -258,6 +300,9 @@ fn test_limit_order_errors() {
         price: Price(0),
         time_in_force: None,
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l2);
     assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::InvalidPrice).code));
@@ -271,6 +316,9 @@ fn test_limit_order_errors() {
             price: Price(1500),
             time_in_force: Some(TimeInForce::FOK),
             post_only: None,
+            owner: None,
+            session: None,
+            persist: false,
         };
         let resp = ob.limit(opts);
         assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderFOK).code));
@@ -297,6 +345,9 @@ fn test_limit_order_errors() {
             price: Price(500),
             time_in_force: Some(TimeInForce::FOK),
             post_only: None,
+            owner: None,
+            session: None,
+            persist: false,
         };
         let resp = ob.limit(opts);
         assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderFOK).code));
@@ -322,6 +373,9 @@ fn test_limit_order_errors() {
             price: Price(1100),
             time_in_force: None,
             post_only: Some(true),
+            owner: None,
+            session: None,
+            persist: false,
         };
         let resp = ob.limit(l5);
         assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderPostOnly).code));
@@ -332,29 +386,40 @@ fn test_limit_order_errors() {
             price: Price(1000),
             time_in_force: None,
             post_only: Some(true),
+            owner: None,
+            session: None,
+            persist: false,
         };
         let resp = ob.limit(l6);
         assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderPostOnly).code));
 
         // Empty the order book and retry
-        let _ = ob.market(MarketOrderOptions { side: Side::Buy, quantity: Quantity(50) });
+        let _ =
+            ob.market(MarketOrderOptions { side: Side::Buy, quantity: Quantity(50), owner: None });
         let l7 = LimitOrderOptions {
             side: Side::Buy,
             quantity: Quantity(6),
             price: Price(1000),
             time_in_force: None,
             post_only: Some(true),
+            owner: None,
+            session: None,
+            persist: false,
         };
         let resp = ob.limit(l7);
         assert!(resp.is_ok());
 
-        let _ = ob.market(MarketOrderOptions { side: Side::Sell, quantity: Quantity(50) });
+        let _ =
+            ob.market(MarketOrderOptions { side: Side::Sell, quantity: Quantity(50), owner: None });
         let l8 = LimitOrderOptions {
             side: Side::Sell,
             quantity: Quantity(6),
             price: Price(1100),
             time_in_force: None,
             post_only: Some(true),
+            owner: None,
+            session: None,
+            persist: false,
         };
         let resp = ob.limit(l8);
         assert!(resp.is_ok());
@@ -375,6 +440,9 @@ fn test_cancel_order() {
         price: Price(1000),
         time_in_force: None,
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l1);
     let resp = resp.unwrap();
@@ -390,6 +458,9 @@ fn test_cancel_order() {
         price: Price(1100),
         time_in_force: None,
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l2);
     let resp = resp.unwrap();
@@ -405,6 +476,9 @@ fn test_cancel_order() {
         price: Price(1200),
         time_in_force: None,
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l3);
     let resp = resp.unwrap();
@@ -423,7 +497,7 @@ fn test_cancel_order() {
         // test cancel order journaling
         let mut ob = get_populated_order_book(
             vec![(Side::Buy, Quantity(5), Price(1000)), (Side::Sell, Quantity(5), Price(1100))],
-            Some(OrderBookOptions { journaling: true, snapshot: None, replay_logs: None }),
+            Some(OrderBookOptions { journaling: true, ..Default::default() }),
         );
 
         // on same price level
@@ -433,6 +507,9 @@ fn test_cancel_order() {
             price: Price(1000),
             time_in_force: None,
             post_only: None,
+            owner: None,
+            session: None,
+            persist: false,
         };
         let resp = ob.limit(l1);
         let resp = resp.unwrap();
@@ -457,6 +534,9 @@ fn test_modify_order() {
         price: Price(1000),
         time_in_force: None,
         post_only: None,
+        owner: None,
+        session: None,
+        persist: false,
     };
     let resp = ob.limit(l1);
     let resp = resp.unwrap();
@@ -587,8 +667,8 @@ fn test_best_bid_ask_mid_spread() {
     assert_eq!(ob.mid_price(), Some(Price(1050)));
     assert_eq!(ob.spread(), Some(Price(100)));
     // empty the order book
-    let _ = ob.market(MarketOrderOptions { side: Side::Buy, quantity: Quantity(20) });
-    let _ = ob.market(MarketOrderOptions { side: Side::Sell, quantity: Quantity(20) });
+    let _ = ob.market(MarketOrderOptions { side: Side::Buy, quantity: Quantity(20), owner: None });
+    let _ = ob.market(MarketOrderOptions { side: Side::Sell, quantity: Quantity(20), owner: None });
 
     assert_eq!(ob.best_bid(), None);
     assert_eq!(ob.best_ask(), None);
@@ -626,7 +706,7 @@ fn test_snapshot() {
             (Side::Buy, Quantity(5), Price(1000)),
             (Side::Buy, Quantity(5), Price(900)),
         ],
-        Some(OrderBookOptions { journaling: true, snapshot: None, replay_logs: None }),
+        Some(OrderBookOptions { journaling: true, ..Default::default() }),
     );
 
     let snap = ob.snapshot();
@@ -648,7 +728,7 @@ fn test_snapshot_restore() {
             (Side::Buy, Quantity(5), Price(1000)),
             (Side::Buy, Quantity(5), Price(900)),
         ],
-        Some(OrderBookOptions { journaling: true, snapshot: None, replay_logs: None }),
+        Some(OrderBookOptions { journaling: true, ..Default::default() }),
     );
     let mut snap = ob.snapshot();
     // remove timestamp to avoid error for different millis
@@ -683,6 +763,9 @@ fn test_replay_logs() {
             side: Side::Sell,
             time_in_force: None,
             post_only: None,
+            owner: None,
+            session: None,
+            persist: false,
         }),
     };
 
@@ -696,6 +779,9 @@ fn test_replay_logs() {
             side: Side::Buy,
             time_in_force: None,
             post_only: None,
+            owner: None,
+            session: None,
+            persist: false,
         }),
     };
 
@@ -703,7 +789,11 @@ fn test_replay_logs() {
         op_id: 3,
         ts: 1_000,
         op: JournalOp::Market,
-        o: OrderOptions::Market(MarketOrderOptions { quantity: Quantity(10), side: Side::Buy }),
+        o: OrderOptions::Market(MarketOrderOptions {
+            quantity: Quantity(10),
+            side: Side::Buy,
+            owner: None,
+        }),
     };
 
     // Step 2: modify the first order
@@ -759,3 +849,1728 @@ fn test_replay_logs() {
     let result_err = ob.replay_logs(vec![bad_modify_log]);
     assert!(result_err.is_err());
 }
+
+#[test]
+fn test_risk_limits() {
+    let limits = RiskLimits::default()
+        .with_max_order_qty(10)
+        .with_max_notional(20_000)
+        .with_max_open_orders_per_owner(2);
+    let mut ob =
+        make_order_book(Some(OrderBookOptions { risk_limits: Some(limits), ..Default::default() }));
+    let owner = OwnerId(1);
+
+    // max quantity is checked before order validation
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 11, 0, None, None));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::RiskMaxOrderQty).code));
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 11));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::RiskMaxOrderQty).code));
+
+    // max notional
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 10, 2001, None, None));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::RiskMaxNotional).code));
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 10, 2000, None, None));
+    assert!(resp.is_ok());
+    let ask_id =
+        ob.limit(LimitOrderOptions::new(Side::Sell, 5, 3000, None, None)).unwrap().order_id;
+    // 10 @ 2000 + 1 @ 3000 = 23000
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 11));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::RiskMaxOrderQty).code));
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 10));
+    assert!(resp.is_ok());
+    // 5 @ 3000 = 15000
+    assert_eq!(ob.market_order_notional(Side::Buy, Quantity(10)), 15_000);
+
+    // a modify breaking the limits keeps the original order
+    let resp = ob.modify(ask_id, None, Some(Quantity(11)));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::RiskMaxOrderQty).code));
+    let resp = ob.modify(ask_id, Some(Price(4001)), None);
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::RiskMaxNotional).code));
+    assert_eq!(ob.get_order(ask_id).unwrap().remaining_qty(), Quantity(5));
+
+    // max resting orders per owner
+    let o1 = ob.limit(LimitOrderOptions::new(Side::Buy, 1, 100, None, None).with_owner(owner));
+    let _ = ob.limit(LimitOrderOptions::new(Side::Buy, 1, 100, None, None).with_owner(owner));
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 1, 100, None, None).with_owner(owner));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::RiskMaxOpenOrders).code));
+    // orders without owner or with another owner are not affected
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 1, 100, None, None)).is_ok());
+    assert!(ob
+        .limit(LimitOrderOptions::new(Side::Buy, 1, 100, None, None).with_owner(OwnerId(2)))
+        .is_ok());
+    // IOC orders never rest
+    assert!(ob
+        .limit(
+            LimitOrderOptions::new(Side::Buy, 1, 100, Some(TimeInForce::IOC), None)
+                .with_owner(owner)
+        )
+        .is_ok());
+
+    // cancel releases a slot
+    let _ = ob.cancel(o1.unwrap().order_id);
+    assert_eq!(ob.open_orders.get(&owner), Some(&1));
+    assert!(ob
+        .limit(LimitOrderOptions::new(Side::Buy, 1, 100, None, None).with_owner(owner))
+        .is_ok());
+
+    // fully filled orders release their slots
+    let _ = ob.market(MarketOrderOptions::new(Side::Sell, 4));
+    assert_eq!(ob.open_orders.get(&owner), None);
+
+    // modify keeps the owner
+    let resp =
+        ob.limit(LimitOrderOptions::new(Side::Buy, 1, 100, None, None).with_owner(owner)).unwrap();
+    let resp = ob.modify(resp.order_id, Some(Price(90)), None).unwrap();
+    assert_eq!(ob.get_order(resp.order_id).unwrap().owner, Some(owner));
+    assert_eq!(ob.open_orders.get(&owner), Some(&1));
+
+    // counters are rebuilt from a snapshot
+    let mut restored = make_order_book(None);
+    restored.restore_snapshot(ob.snapshot());
+    assert_eq!(restored.open_orders, ob.open_orders);
+}
+
+#[test]
+fn test_price_band() {
+    let band = PriceBand::new(ReferencePrice::External, 1_000, PriceBandAction::Reject);
+    let mut ob = make_order_book(Some(OrderBookOptions {
+        journaling: true,
+        price_band: Some(band),
+        ..Default::default()
+    }));
+
+    // no reference price yet: the band is not enforced
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 5, 5000, None, None)).is_ok());
+
+    assert!(ob.set_reference_price(Price(0)).is_err());
+    let log = ob.set_reference_price(Price(1000)).unwrap().unwrap();
+    assert_eq!(log.op, JournalOp::SetReferencePrice);
+    assert_eq!(log.o, OrderOptions::SetReferencePrice(Price(1000)));
+    assert_eq!(ob.reference_price(), Some(Price(1000)));
+
+    // band is 900 - 1100
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 1101, None, None));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderOutsidePriceBand).code));
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 899, None, None));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderOutsidePriceBand).code));
+    // passive orders outside the band are accepted
+    let bid_id = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 800, None, None)).unwrap().order_id;
+    // a modify outside the band keeps the original order
+    let resp = ob.modify(bid_id, Some(Price(1101)), None);
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderOutsidePriceBand).code));
+    assert!(ob.get_order(bid_id).is_ok());
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1100, None, None)).is_ok());
+
+    // market sweeps stop at the band edge
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 10)).unwrap();
+    assert_eq!(resp.executed_qty, Quantity(5));
+    assert_eq!(resp.remaining_qty, Quantity(5));
+    assert_eq!(ob.best_ask(), Some(Price(5000)));
+    assert_eq!(ob.last_trade_price(), Some(Price(1100)));
+
+    // the reference price is replayed from the journal
+    let mut replayed = make_order_book(None);
+    assert!(replayed.replay_logs(vec![log]).is_ok());
+    assert_eq!(replayed.reference_price(), Some(Price(1000)));
+}
+
+#[test]
+fn test_price_band_cap() {
+    let band = PriceBand::new(ReferencePrice::LastTrade, 1_000, PriceBandAction::Cap);
+    let mut ob =
+        make_order_book(Some(OrderBookOptions { price_band: Some(band), ..Default::default() }));
+    let _ = ob.limit(LimitOrderOptions::new(Side::Sell, 1, 1000, None, None));
+    let _ = ob.market(MarketOrderOptions::new(Side::Buy, 1));
+    assert_eq!(ob.last_trade_price(), Some(Price(1000)));
+
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 2000, None, None)).unwrap();
+    assert_eq!(resp.price, Price(1100));
+    assert_eq!(ob.best_bid(), Some(Price(1100)));
+
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1, None, None)).unwrap();
+    assert_eq!(resp.price, Price(900));
+    // the sell order was capped at 900 and matched the bid at 1100
+    assert_eq!(resp.status, OrderStatus::Filled);
+    assert_eq!(ob.last_trade_price(), Some(Price(1100)));
+
+    // band state is persisted in the snapshot
+    let snap = ob.snapshot();
+    assert_eq!(snap.last_trade_price, Some(Price(1100)));
+    let mut restored = make_order_book(None);
+    restored.restore_snapshot(snap);
+    assert_eq!(restored.last_trade_price(), Some(Price(1100)));
+}
+
+#[test]
+fn test_price_band_mid() {
+    let band = PriceBand::new(ReferencePrice::Mid, 500, PriceBandAction::Reject);
+    let mut ob = get_populated_order_book(
+        vec![(Side::Buy, Quantity(5), Price(900)), (Side::Sell, Quantity(5), Price(1100))],
+        Some(OrderBookOptions { price_band: Some(band), ..Default::default() }),
+    );
+    // mid is 1000, band is 950 - 1050
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 1051, None, None));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderOutsidePriceBand).code));
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 5, 1050, None, None)).is_ok());
+}
+
+#[test]
+fn test_fill_report_quantity_of_partially_filled_maker() {
+    let mut ob = get_populated_order_book(vec![(Side::Sell, Quantity(10), Price(1000))], None);
+    let _ = ob.market(MarketOrderOptions::new(Side::Buy, 4));
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 6)).unwrap();
+    assert_eq!(resp.fills.len(), 1);
+    assert_eq!(resp.fills[0].quantity, Quantity(6));
+    assert_eq!(resp.fills[0].status, OrderStatus::Filled);
+}
+
+#[test]
+fn test_accounts() {
+    let alice = OwnerId(1);
+    let bob = OwnerId(2);
+    let mut ob = make_order_book(Some(OrderBookOptions {
+        journaling: true,
+        accounts: true,
+        ..Default::default()
+    }));
+    let mut logs = Vec::new();
+    logs.push(ob.deposit(alice, Asset::Base, 10).unwrap().unwrap());
+    logs.push(ob.deposit(bob, Asset::Quote, 10_000).unwrap().unwrap());
+
+    // orders must have an owner
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1000, None, None));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OwnerRequired).code));
+
+    // insufficient free balance
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 11, 1000, None, None).with_owner(alice));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::InsufficientBalance).code));
+
+    // Alice sells 6 @ 1000 and 4 @ 1100: all her base is reserved
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 6, 1000, None, None).with_owner(alice));
+    logs.push(resp.unwrap().log.unwrap());
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 4, 1100, None, None).with_owner(alice));
+    let ask_id = resp.as_ref().unwrap().order_id;
+    logs.push(resp.unwrap().log.unwrap());
+    assert_eq!(ob.balance(alice).unwrap().base, Balance { free: 0, reserved: 10 });
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 1, 1000, None, None).with_owner(alice));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::InsufficientBalance).code));
+
+    // Bob buys 8 with a limit at 1200: 6 @ 1000 + 2 @ 1100 = 8200
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 8, 1200, None, None).with_owner(bob));
+    logs.push(resp.unwrap().log.unwrap());
+    assert_eq!(ob.balance(bob).unwrap().base, Balance { free: 8, reserved: 0 });
+    assert_eq!(ob.balance(bob).unwrap().quote, Balance { free: 1800, reserved: 0 });
+    assert_eq!(ob.balance(alice).unwrap().base, Balance { free: 0, reserved: 2 });
+    assert_eq!(ob.balance(alice).unwrap().quote, Balance { free: 8200, reserved: 0 });
+
+    // Bob rests a bid: 1 @ 900 reserved
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 1, 900, None, None).with_owner(bob));
+    let bid_id = resp.as_ref().unwrap().order_id;
+    logs.push(resp.unwrap().log.unwrap());
+    assert_eq!(ob.balance(bob).unwrap().quote, Balance { free: 900, reserved: 900 });
+
+    // a modify is checked against the funds the order frees: 3 @ 900 needs 1800 more
+    let resp = ob.modify(bid_id, None, Some(Quantity(3)));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::InsufficientBalance).code));
+    assert!(ob.get_order(bid_id).is_ok());
+    assert_eq!(ob.balance(bob).unwrap().quote, Balance { free: 900, reserved: 900 });
+
+    // market orders are paid from the free balance
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 1).with_owner(bob));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::InsufficientBalance).code));
+    let resp = ob.market(MarketOrderOptions::new(Side::Sell, 3).with_owner(bob));
+    logs.push(resp.unwrap().log.unwrap());
+    // Bob sold 1 to himself at 900
+    assert_eq!(ob.balance(bob).unwrap().base, Balance { free: 8, reserved: 0 });
+    assert_eq!(ob.balance(bob).unwrap().quote, Balance { free: 1800, reserved: 0 });
+    assert!(ob.get_order(bid_id).is_err());
+
+    // cancel releases the reserved funds
+    logs.push(ob.cancel(ask_id).unwrap().log.unwrap());
+    assert_eq!(ob.balance(alice).unwrap().base, Balance { free: 2, reserved: 0 });
+
+    // withdraw from the free balance only
+    let resp = ob.withdraw(alice, Asset::Quote, 8201);
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::InsufficientBalance).code));
+    logs.push(ob.withdraw(alice, Asset::Quote, 8200).unwrap().unwrap());
+    assert_eq!(ob.balance(alice).unwrap().quote, Balance { free: 0, reserved: 0 });
+
+    // the ledger is rebuilt by the journal and persisted in the snapshot
+    let mut replayed =
+        make_order_book(Some(OrderBookOptions { accounts: true, ..Default::default() }));
+    assert!(replayed.replay_logs(logs).is_ok());
+    assert_eq!(replayed.balance(alice), ob.balance(alice));
+    assert_eq!(replayed.balance(bob), ob.balance(bob));
+
+    let mut restored =
+        make_order_book(Some(OrderBookOptions { accounts: true, ..Default::default() }));
+    restored.restore_snapshot(ob.snapshot());
+    assert_eq!(restored.balance(bob), ob.balance(bob));
+
+    // the ledger is not enabled by a snapshot
+    let mut restored = make_order_book(None);
+    restored.restore_snapshot(ob.snapshot());
+    assert_eq!(restored.balance(bob), None);
+    assert!(restored.limit(LimitOrderOptions::new(Side::Buy, 1, 900, None, None)).is_ok());
+
+    // accounts must be enabled
+    let mut ob = make_order_book(None);
+    let resp = ob.deposit(alice, Asset::Base, 1);
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::AccountsDisabled).code));
+    assert!(ob.balance(alice).is_none());
+}
+
+#[test]
+fn test_positions() {
+    let alice = OwnerId(1);
+    let bob = OwnerId(2);
+    let mut ob = make_order_book(Some(OrderBookOptions { positions: true, ..Default::default() }));
+
+    let _ = ob.limit(LimitOrderOptions::new(Side::Sell, 10, 1000, None, None).with_owner(alice));
+    let _ = ob.market(MarketOrderOptions::new(Side::Buy, 4).with_owner(bob));
+    let _ = ob.limit(LimitOrderOptions::new(Side::Buy, 6, 1100, None, None).with_owner(bob));
+
+    let alice_position = ob.position(alice).unwrap();
+    assert_eq!(alice_position.quantity, -10);
+    assert_eq!(alice_position.avg_entry_price(), Some(Price(1000)));
+    let bob_position = ob.position(bob).unwrap();
+    assert_eq!(bob_position.quantity, 10);
+    assert_eq!(bob_position.cost, 10_000);
+
+    // Bob sells 5 to a resting bid at 1200
+    let _ = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 1200, None, None));
+    let _ = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 900, None, None).with_owner(bob));
+    let bob_position = ob.position(bob).unwrap();
+    assert_eq!(bob_position.quantity, 5);
+    assert_eq!(bob_position.realized_pnl, 1000);
+
+    // unrealized PnL at last trade (1200) and at mid
+    assert_eq!(ob.unrealized_pnl(bob, ReferencePrice::LastTrade), Some(1000));
+    assert_eq!(ob.unrealized_pnl(alice, ReferencePrice::LastTrade), Some(-2000));
+    assert_eq!(ob.unrealized_pnl(bob, ReferencePrice::Mid), None);
+    let _ = ob.limit(LimitOrderOptions::new(Side::Buy, 1, 1000, None, None));
+    let _ = ob.limit(LimitOrderOptions::new(Side::Sell, 1, 1100, None, None));
+    assert_eq!(ob.unrealized_pnl(bob, ReferencePrice::Mid), Some(250));
+    assert_eq!(ob.unrealized_pnl(OwnerId(3), ReferencePrice::Mid), None);
+
+    // positions are persisted in the snapshot
+    let mut restored =
+        make_order_book(Some(OrderBookOptions { positions: true, ..Default::default() }));
+    restored.restore_snapshot(ob.snapshot());
+    assert_eq!(restored.position(bob), ob.position(bob));
+
+    // position tracking is not enabled by a snapshot
+    let mut restored = make_order_book(None);
+    restored.restore_snapshot(ob.snapshot());
+    assert_eq!(restored.position(bob), None);
+}
+
+#[test]
+fn test_fees() {
+    let alice = OwnerId(1);
+    let bob = OwnerId(2);
+    let options = OrderBookOptions {
+        journaling: true,
+        accounts: true,
+        positions: true,
+        fees: Some(FeeSchedule::new(-2, 10).with_tier(10_000, -5, 5)),
+        ..Default::default()
+    };
+    let mut ob = make_order_book(Some(options.clone()));
+    let mut logs = Vec::new();
+    logs.push(ob.deposit(alice, Asset::Base, 12).unwrap().unwrap());
+    logs.push(ob.deposit(bob, Asset::Quote, 20_000).unwrap().unwrap());
+
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 10, 1000, None, None).with_owner(alice));
+    logs.push(resp.unwrap().log.unwrap());
+
+    // a buy reserves the notional plus the fee at the highest rate
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 1000, None, None).with_owner(bob));
+    let report = resp.unwrap();
+    assert_eq!(report.fills[0].maker_fee, -1);
+    assert_eq!(report.fills[0].taker_fee, 5);
+    assert_eq!(report.fees, 5);
+    logs.push(report.log.unwrap());
+    assert_eq!(ob.balance(bob).unwrap().quote, Balance { free: 14_995, reserved: 0 });
+    assert_eq!(ob.balance(alice).unwrap().quote, Balance { free: 5001, reserved: 0 });
+
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 5).with_owner(bob));
+    let report = resp.unwrap();
+    assert_eq!(report.fees, 5);
+    logs.push(report.log.unwrap());
+    assert_eq!(ob.traded_volume(alice), Some(10_000));
+    assert_eq!(ob.traded_volume(bob), Some(10_000));
+
+    // both owners reached the second tier
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 2, 1000, None, None).with_owner(alice));
+    logs.push(resp.unwrap().log.unwrap());
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 2).with_owner(bob));
+    let report = resp.unwrap();
+    assert_eq!(report.fills[0].maker_fee, -1);
+    assert_eq!(report.fees, 1);
+    logs.push(report.log.unwrap());
+
+    assert_eq!(ob.balance(bob).unwrap().quote, Balance { free: 7989, reserved: 0 });
+    assert_eq!(ob.balance(alice).unwrap().quote, Balance { free: 12_003, reserved: 0 });
+    assert_eq!(ob.position(bob).unwrap().realized_pnl, -11);
+    assert_eq!(ob.position(alice).unwrap().realized_pnl, 3);
+
+    // the traded volumes are rebuilt by the journal and persisted in the snapshot
+    let mut replayed =
+        make_order_book(Some(OrderBookOptions { journaling: false, ..options.clone() }));
+    assert!(replayed.replay_logs(logs).is_ok());
+    assert_eq!(replayed.balance(bob), ob.balance(bob));
+    assert_eq!(replayed.traded_volume(bob), Some(12_000));
+
+    let mut restored = make_order_book(Some(options));
+    restored.restore_snapshot(ob.snapshot());
+    assert_eq!(restored.traded_volume(alice), Some(12_000));
+    assert_eq!(make_order_book(None).traded_volume(alice), None);
+}
+
+#[test]
+fn test_close_session() {
+    let s1 = SessionId(1);
+    let s2 = SessionId(2);
+    let mut ob = make_order_book(Some(OrderBookOptions { journaling: true, ..Default::default() }));
+    let mut logs = Vec::new();
+
+    let mut ids = Vec::new();
+    for (price, session, persist) in [
+        (1000, Some(s1), false),
+        (1010, Some(s1), true),
+        (1020, Some(s2), false),
+        (1030, None, false),
+    ] {
+        let mut options =
+            LimitOrderOptions::new(Side::Sell, 5, price, None, None).with_persist(persist);
+        options.session = session;
+        let resp = ob.limit(options).unwrap();
+        ids.push(resp.order_id);
+        logs.push(resp.log.unwrap());
+    }
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 900, None, None).with_session(s1));
+    logs.push(resp.unwrap().log.unwrap());
+
+    // the replacement order keeps the session of the modified one
+    let resp = ob.modify(ids[0], Some(Price(1005)), None).unwrap();
+    let modified_id = resp.order_id;
+    logs.push(resp.log.unwrap());
+
+    let reports = ob.close_session(s1);
+    assert_eq!(reports.len(), 2);
+    assert!(reports.iter().all(|r| r.status == OrderStatus::Canceled));
+    assert_eq!(reports[1].order_id, modified_id);
+    logs.extend(reports.into_iter().map(|r| r.log.unwrap()));
+
+    assert!(ob.get_order(modified_id).is_err());
+    assert!(ob.get_order(ids[1]).is_ok());
+    assert!(ob.get_order(ids[2]).is_ok());
+    assert!(ob.get_order(ids[3]).is_ok());
+    assert!(ob.bids.is_empty());
+
+    // closing again is a no-op
+    assert!(ob.close_session(s1).is_empty());
+
+    // sessions are closed whatever the trading phase
+    logs.push(ob.set_phase(TradingPhase::Closed).unwrap().unwrap());
+    assert!(ob.cancel(ids[2]).is_err());
+    let reports = ob.close_session(s2);
+    assert_eq!(reports.iter().map(|r| r.order_id).collect::<Vec<_>>(), vec![ids[2]]);
+    let log = reports[0].log.unwrap();
+    assert_eq!(log.op, JournalOp::SessionCancel);
+    logs.push(log);
+    assert!(ob.get_order(ids[2]).is_err());
+
+    let mut replayed = make_order_book(None);
+    assert!(replayed.replay_logs(logs).is_ok());
+    let mut replayed_ids: Vec<u64> = replayed.orders.keys().map(|id| id.0).collect();
+    replayed_ids.sort();
+    assert_eq!(replayed_ids, vec![ids[1].0, ids[3].0]);
+}
+
+#[test]
+fn test_throttle_message_rate() {
+    let alice = OwnerId(1);
+    let bob = OwnerId(2);
+    let throttle = ThrottleLimits::default().with_max_messages(3, 1000);
+    let mut ob = make_order_book(Some(OrderBookOptions {
+        journaling: true,
+        throttle: Some(throttle),
+        ..Default::default()
+    }));
+    let mut logs = Vec::new();
+
+    ob.set_clock(Some(1000));
+    assert_eq!(ob.now(), 1000);
+    let mut ids = Vec::new();
+    for price in [1000, 1010, 1020] {
+        let resp =
+            ob.limit(LimitOrderOptions::new(Side::Sell, 5, price, None, None).with_owner(alice));
+        ids.push(resp.as_ref().unwrap().order_id);
+        logs.push(resp.unwrap().log.unwrap());
+    }
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1030, None, None).with_owner(alice));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::ThrottleMessageRate).code));
+    let resp = ob.cancel(ids[0]);
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::ThrottleMessageRate).code));
+    // other owners and anonymous orders are not affected
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 1, 900, None, None).with_owner(bob));
+    logs.push(resp.unwrap().log.unwrap());
+    logs.push(
+        ob.limit(LimitOrderOptions::new(Side::Buy, 1, 900, None, None)).unwrap().log.unwrap(),
+    );
+
+    // the window slides with the engine clock
+    ob.set_clock(Some(1999));
+    assert!(ob.cancel(ids[0]).is_err());
+    ob.set_clock(Some(2000));
+    logs.push(ob.cancel(ids[0]).unwrap().log.unwrap());
+    logs.push(ob.modify(ids[1], Some(Price(1015)), None).unwrap().log.unwrap());
+    assert_eq!(ob.throttle_activity(alice).unwrap().messages, VecDeque::from([2000, 2000]));
+    assert_eq!(ob.throttle_activity(alice).unwrap().orders, 4);
+
+    // replay follows the timestamps of the journal
+    let mut replayed =
+        make_order_book(Some(OrderBookOptions { throttle: Some(throttle), ..Default::default() }));
+    assert!(replayed.replay_logs(logs).is_ok());
+    assert_eq!(replayed.throttle_activity(alice), ob.throttle_activity(alice));
+    assert_eq!(replayed.clock, None);
+
+    let mut restored =
+        make_order_book(Some(OrderBookOptions { throttle: Some(throttle), ..Default::default() }));
+    restored.restore_snapshot(ob.snapshot());
+    assert_eq!(restored.throttle_activity(bob), ob.throttle_activity(bob));
+}
+
+#[test]
+fn test_throttle_order_to_trade_ratio() {
+    let alice = OwnerId(1);
+    let mut ob = make_order_book(Some(OrderBookOptions {
+        throttle: Some(ThrottleLimits::default().with_max_order_to_trade_ratio(2, 0)),
+        ..Default::default()
+    }));
+
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1000, None, None).with_owner(alice));
+    let ask_id = resp.unwrap().order_id;
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1010, None, None).with_owner(alice));
+    assert!(resp.is_ok());
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1020, None, None).with_owner(alice));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::ThrottleOrderToTradeRatio).code));
+
+    // trades raise the allowance
+    assert!(ob.market(MarketOrderOptions::new(Side::Buy, 1)).is_ok());
+    assert!(ob.market(MarketOrderOptions::new(Side::Buy, 1)).is_ok());
+    assert_eq!(ob.throttle_activity(alice).unwrap().trades, 2);
+
+    // a modify counts as a single order and cancels are always allowed
+    let resp = ob.modify(ask_id, None, Some(Quantity(2)));
+    let ask_id = resp.unwrap().order_id;
+    assert_eq!(ob.throttle_activity(alice).unwrap().orders, 3);
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1020, None, None).with_owner(alice));
+    assert!(resp.is_ok());
+    let resp = ob.modify(ask_id, None, Some(Quantity(1)));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::ThrottleOrderToTradeRatio).code));
+    assert!(ob.get_order(ask_id).is_ok());
+    assert!(ob.cancel(ask_id).is_ok());
+}
+
+#[test]
+fn test_throttle_session_close_and_rejected_modify() {
+    let alice = OwnerId(1);
+    let session = SessionId(1);
+    let throttle = ThrottleLimits::default().with_max_messages(3, 1000);
+    let mut ob =
+        make_order_book(Some(OrderBookOptions { throttle: Some(throttle), ..Default::default() }));
+    ob.set_clock(Some(1000));
+
+    let mut ids = Vec::new();
+    for price in [1000, 1010] {
+        let opts = LimitOrderOptions::new(Side::Sell, 5, price, None, None)
+            .with_owner(alice)
+            .with_session(session);
+        ids.push(ob.limit(opts).unwrap().order_id);
+    }
+    // a rejected replacement does not count
+    assert!(ob.modify(ids.remove(0), Some(Price(0)), None).is_err());
+    assert_eq!(ob.throttle_activity(alice).unwrap().messages.len(), 2);
+    let opts = LimitOrderOptions::new(Side::Sell, 5, 1020, None, None)
+        .with_owner(alice)
+        .with_session(session);
+    ids.push(ob.limit(opts).unwrap().order_id);
+    assert!(ob.cancel(ids[0]).is_err());
+
+    // closing the session cancels every order of a throttled owner
+    let reports = ob.close_session(session);
+    assert_eq!(reports.iter().map(|r| r.order_id).collect::<Vec<_>>(), ids);
+    assert_eq!(ob.throttle_activity(alice).unwrap().messages.len(), 3);
+}
+
+#[test]
+fn test_trading_phases() {
+    let mut ob = make_order_book(Some(OrderBookOptions { journaling: true, ..Default::default() }));
+    let mut logs = Vec::new();
+    assert_eq!(ob.phase(), TradingPhase::Continuous);
+
+    let resp = ob.set_phase(TradingPhase::Continuous);
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::InvalidPhaseTransition).code));
+    logs.push(ob.set_phase(TradingPhase::Closed).unwrap().unwrap());
+
+    // nothing is accepted once closed
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1000, None, None));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::MarketClosed).code));
+    let resp = ob.cancel(OrderId(0));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::MarketClosed).code));
+
+    // pre-open collects limit orders without matching
+    logs.push(ob.set_phase(TradingPhase::PreOpen).unwrap().unwrap());
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1000, None, None));
+    let ask_id = resp.as_ref().unwrap().order_id;
+    logs.push(resp.unwrap().log.unwrap());
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 1100, None, None));
+    let bid_id = resp.as_ref().unwrap().order_id;
+    assert_eq!(resp.as_ref().unwrap().executed_qty, Quantity(0));
+    assert!(resp.as_ref().unwrap().fills.is_empty());
+    logs.push(resp.unwrap().log.unwrap());
+    let resp = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 1100, Some(TimeInForce::IOC), None));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderNotAllowedInPhase).code));
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 1));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderNotAllowedInPhase).code));
+    let resp = ob.modify(bid_id, None, Some(Quantity(4))).unwrap();
+    let bid_id = resp.order_id;
+    logs.push(resp.log.unwrap());
+    assert_eq!(ob.best_bid(), Some(Price(1100)));
+    assert_eq!(ob.best_ask(), Some(Price(1000)));
+
+    // only cancels while halted
+    logs.push(ob.set_phase(TradingPhase::Halted).unwrap().unwrap());
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1000, None, None));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::TradingHalted).code));
+    let resp = ob.modify(bid_id, Some(Price(1050)), None);
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::TradingHalted).code));
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 1));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::TradingHalted).code));
+    logs.push(ob.cancel(ask_id).unwrap().log.unwrap());
+
+    logs.push(ob.set_phase(TradingPhase::Continuous).unwrap().unwrap());
+    let resp = ob.market(MarketOrderOptions::new(Side::Sell, 1));
+    assert_eq!(resp.unwrap().fills.len(), 1);
+
+    // phase changes are journaled and persisted in the snapshot
+    let mut replayed = make_order_book(None);
+    assert!(replayed.replay_logs(logs).is_ok());
+    assert_eq!(replayed.phase(), TradingPhase::Continuous);
+    assert_eq!(replayed.best_bid(), Some(Price(1100)));
+    assert!(replayed.best_ask().is_none());
+
+    ob.set_phase(TradingPhase::Halted).unwrap();
+    let mut restored = make_order_book(None);
+    restored.restore_snapshot(ob.snapshot());
+    assert_eq!(restored.phase(), TradingPhase::Halted);
+}
+
+#[test]
+fn test_uncross() {
+    let alice = OwnerId(1);
+    let bob = OwnerId(2);
+    let carol = OwnerId(3);
+    let mut ob = make_order_book(Some(OrderBookOptions {
+        journaling: true,
+        accounts: true,
+        ..Default::default()
+    }));
+    let mut logs = Vec::new();
+    logs.push(ob.deposit(alice, Asset::Base, 20).unwrap().unwrap());
+    logs.push(ob.deposit(bob, Asset::Quote, 100_000).unwrap().unwrap());
+    logs.push(ob.deposit(carol, Asset::Quote, 100_000).unwrap().unwrap());
+
+    let resp = ob.uncross();
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::NotInAuction).code));
+
+    logs.push(ob.set_phase(TradingPhase::Auction).unwrap().unwrap());
+    for (owner, side, quantity, price) in [
+        (bob, Side::Buy, 10, 1100),
+        (carol, Side::Buy, 5, 1000),
+        (alice, Side::Sell, 8, 950),
+        (alice, Side::Sell, 6, 1050),
+    ] {
+        let options = LimitOrderOptions::new(side, quantity, price, None, None).with_owner(owner);
+        logs.push(ob.limit(options).unwrap().log.unwrap());
+    }
+
+    // 10 can be executed at 1050 and 1100 with a sell surplus of 4: the lowest price
+    let indicative = ob.indicative_auction().unwrap();
+    assert_eq!(indicative.price, Price(1050));
+    assert_eq!(indicative.matched_qty, Quantity(10));
+    assert_eq!(indicative.surplus_qty, Quantity(4));
+    assert_eq!(indicative.surplus_side, Some(Side::Sell));
+    assert_eq!(ob.best_bid(), Some(Price(1100)));
+    let report = ob.uncross().unwrap();
+    logs.push(report.log.unwrap());
+    assert_eq!(report.price, Some(Price(1050)));
+    assert_eq!(report.quantity, Quantity(10));
+    let fills: Vec<_> =
+        report.fills.iter().map(|f| (f.order_id.0, f.price.0, f.quantity.0, f.status)).collect();
+    assert_eq!(
+        fills,
+        vec![
+            (0, 1050, 8, OrderStatus::PartiallyFilled),
+            (2, 1050, 8, OrderStatus::Filled),
+            (0, 1050, 2, OrderStatus::Filled),
+            (3, 1050, 2, OrderStatus::PartiallyFilled),
+        ]
+    );
+
+    assert_eq!(ob.best_bid(), Some(Price(1000)));
+    assert_eq!(ob.best_ask(), Some(Price(1050)));
+    assert_eq!(ob.get_order(OrderId(3)).unwrap().remaining_qty(), Quantity(4));
+    assert_eq!(ob.last_trade_price(), Some(Price(1050)));
+    // the buyer gets back the reservation made at its limit price
+    assert_eq!(ob.balance(bob).unwrap().quote, Balance { free: 89_500, reserved: 0 });
+    assert_eq!(ob.balance(bob).unwrap().base, Balance { free: 10, reserved: 0 });
+    assert_eq!(ob.balance(alice).unwrap().quote, Balance { free: 10_500, reserved: 0 });
+    assert_eq!(ob.balance(alice).unwrap().base, Balance { free: 6, reserved: 4 });
+
+    // a book that is not crossed has nothing to execute
+    assert_eq!(ob.indicative_auction(), None);
+    let report = ob.uncross().unwrap();
+    assert_eq!(report.price, None);
+    assert!(report.fills.is_empty());
+    logs.push(report.log.unwrap());
+
+    let mut replayed =
+        make_order_book(Some(OrderBookOptions { accounts: true, ..Default::default() }));
+    assert!(replayed.replay_logs(logs).is_ok());
+    assert_eq!(replayed.balance(alice), ob.balance(alice));
+    assert_eq!(replayed.balance(bob), ob.balance(bob));
+    assert_eq!(replayed.depth(None), ob.depth(None));
+}
+
+#[test]
+fn test_auction_only_orders() {
+    let mut ob = make_order_book(Some(OrderBookOptions { journaling: true, ..Default::default() }));
+    let mut logs = Vec::new();
+    logs.push(
+        ob.limit(LimitOrderOptions::new(Side::Sell, 5, 1000, None, None)).unwrap().log.unwrap(),
+    );
+    let report = ob.limit_on_open(LimitOrderOptions::new(Side::Buy, 4, 1100, None, None)).unwrap();
+    assert_eq!(report.order_type, OrderType::LimitOnOpen);
+    logs.push(report.log.unwrap());
+    let report = ob.market_on_open(MarketOrderOptions::new(Side::Buy, 5)).unwrap();
+    assert_eq!(report.order_type, OrderType::MarketOnOpen);
+    logs.push(report.log.unwrap());
+    logs.push(ob.market_on_open(MarketOrderOptions::new(Side::Sell, 2)).unwrap().log.unwrap());
+    let options = LimitOrderOptions::new(Side::Buy, 10, 2000, None, None);
+    logs.push(ob.limit_on_close(options).unwrap().log.unwrap());
+
+    // auction-only orders stay outside the book until their auction
+    assert_eq!(ob.best_bid(), None);
+    assert_eq!(ob.best_ask(), Some(Price(1000)));
+    assert_eq!(ob.get_order(OrderId(1)).unwrap().order_type, OrderType::LimitOnOpen);
+    let resp = ob.modify(OrderId(1), Some(Price(1050)), None);
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::AuctionOrderNotModifiable).code));
+    assert!(ob.get_order(OrderId(1)).is_ok());
+    let resp = ob.market_on_open(MarketOrderOptions::new(Side::Buy, 0));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::InvalidQuantity).code));
+
+    logs.push(ob.set_phase(TradingPhase::Closed).unwrap().unwrap());
+    let resp = ob.market_on_open(MarketOrderOptions::new(Side::Buy, 1));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::MarketClosed).code));
+    logs.push(ob.set_phase(TradingPhase::PreOpen).unwrap().unwrap());
+
+    // 7 can be executed at 1000 and 1100 with a buy surplus of 2: the highest price
+    let indicative = ob.indicative_auction().unwrap();
+    assert_eq!(indicative.price, Price(1100));
+    assert_eq!(indicative.matched_qty, Quantity(7));
+    assert_eq!(indicative.surplus_side, Some(Side::Buy));
+
+    let report = ob.uncross().unwrap();
+    logs.push(report.log.unwrap());
+    assert_eq!(report.price, Some(Price(1100)));
+    let fills: Vec<_> =
+        report.fills.iter().map(|f| (f.order_id.0, f.price.0, f.quantity.0)).collect();
+    // market orders first, then limit orders
+    assert_eq!(
+        fills,
+        vec![(2, 1100, 2), (3, 1100, 2), (2, 1100, 3), (0, 1100, 3), (1, 1100, 2), (0, 1100, 2)]
+    );
+    // the remainder of the limit-on-open order is canceled
+    assert_eq!(report.canceled, vec![OrderId(1)]);
+    assert!(ob.get_order(OrderId(1)).is_err());
+    assert_eq!(ob.best_bid(), None);
+    assert_eq!(ob.best_ask(), None);
+
+    // the limit-on-close order waits for the closing auction
+    assert_eq!(ob.snapshot().auction_orders.keys().collect::<Vec<_>>(), vec![&OrderId(4)]);
+    let mut replayed = make_order_book(None);
+    assert!(replayed.replay_logs(logs).is_ok());
+    assert_eq!(replayed.get_order(OrderId(4)), ob.get_order(OrderId(4)));
+    assert_eq!(replayed.last_trade_price(), Some(Price(1100)));
+    assert!(ob.cancel(OrderId(4)).is_ok());
+    assert!(ob.get_order(OrderId(4)).is_err());
+
+    // the price of a market buy is unknown until the auction
+    let mut ob = make_order_book(Some(OrderBookOptions { accounts: true, ..Default::default() }));
+    let resp = ob.market_on_close(MarketOrderOptions::new(Side::Buy, 1).with_owner(OwnerId(1)));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::AuctionMarketBuyUnfunded).code));
+}
+
+#[test]
+fn test_volatility_interruption() {
+    let make_book = |phase, remainder| {
+        let vi = VolatilityInterruption::new(phase, remainder).with_static_bps(500);
+        let mut ob = make_order_book(Some(OrderBookOptions {
+            journaling: true,
+            volatility_interruption: Some(vi),
+            ..Default::default()
+        }));
+        let mut logs = vec![ob.set_reference_price(Price(1000)).unwrap().unwrap()];
+        for price in [1000, 1040, 1100] {
+            let options = LimitOrderOptions::new(Side::Sell, 5, price, None, None);
+            logs.push(ob.limit(options).unwrap().log.unwrap());
+        }
+        (ob, logs)
+    };
+
+    // the sweep stops before trading outside 950..=1050 and the book is halted
+    let (mut ob, mut logs) = make_book(InterruptionPhase::Halted, InterruptionRemainder::Cancel);
+    let report = ob.market(MarketOrderOptions::new(Side::Buy, 20)).unwrap();
+    logs.push(report.log.unwrap());
+    assert_eq!(report.executed_qty, Quantity(10));
+    assert_eq!(report.remaining_qty, Quantity(10));
+    assert_eq!(ob.phase(), TradingPhase::Halted);
+    assert_eq!(ob.best_ask(), Some(Price(1100)));
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 1));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::TradingHalted).code));
+
+    let mut replayed = make_order_book(Some(OrderBookOptions {
+        volatility_interruption: ob.volatility_interruption,
+        ..Default::default()
+    }));
+    assert!(replayed.replay_logs(logs).is_ok());
+    assert_eq!(replayed.phase(), TradingPhase::Halted);
+    assert_eq!(replayed.depth(None), ob.depth(None));
+
+    // the remainder of an interrupted limit order is canceled
+    let (mut ob, _) = make_book(InterruptionPhase::Auction, InterruptionRemainder::Cancel);
+    let report = ob.limit(LimitOrderOptions::new(Side::Buy, 20, 1200, None, None)).unwrap();
+    assert_eq!(report.executed_qty, Quantity(10));
+    assert_eq!(report.status, OrderStatus::Canceled);
+    assert_eq!(ob.phase(), TradingPhase::Auction);
+    assert_eq!(ob.best_bid(), None);
+
+    // or rests in the book, collected for the volatility auction
+    let (mut ob, _) = make_book(InterruptionPhase::Auction, InterruptionRemainder::Rest);
+    let report = ob.limit(LimitOrderOptions::new(Side::Buy, 20, 1200, None, None)).unwrap();
+    assert_eq!(report.executed_qty, Quantity(10));
+    assert_eq!(ob.phase(), TradingPhase::Auction);
+    assert_eq!(ob.best_bid(), Some(Price(1200)));
+    // buy surplus at both 1100 and 1200: the highest price
+    let report = ob.uncross().unwrap();
+    assert_eq!(report.price, Some(Price(1200)));
+    assert_eq!(report.quantity, Quantity(5));
+
+    // but never in a halted book, which it would cross
+    let (mut ob, _) = make_book(InterruptionPhase::Halted, InterruptionRemainder::Rest);
+    let report = ob.limit(LimitOrderOptions::new(Side::Buy, 20, 1200, None, None)).unwrap();
+    assert_eq!(report.executed_qty, Quantity(10));
+    assert_eq!(report.status, OrderStatus::Canceled);
+    assert_eq!(ob.phase(), TradingPhase::Halted);
+    assert_eq!(ob.best_bid(), None);
+    assert_eq!(ob.best_ask(), Some(Price(1100)));
+
+    // fill-or-kill orders only count the liquidity within the range
+    let (mut ob, _) = make_book(InterruptionPhase::Halted, InterruptionRemainder::Cancel);
+    let options = LimitOrderOptions::new(Side::Buy, 15, 1100, Some(TimeInForce::FOK), None);
+    let resp = ob.limit(options);
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::OrderFOK).code));
+    assert_eq!(ob.phase(), TradingPhase::Continuous);
+    assert_eq!(ob.best_ask(), Some(Price(1000)));
+    let options = LimitOrderOptions::new(Side::Buy, 10, 1100, Some(TimeInForce::FOK), None);
+    assert_eq!(ob.limit(options).unwrap().status, OrderStatus::Filled);
+
+    // a trade within the range does not interrupt
+    let (mut ob, _) = make_book(InterruptionPhase::Halted, InterruptionRemainder::Cancel);
+    assert!(ob.market(MarketOrderOptions::new(Side::Buy, 10)).is_ok());
+    assert_eq!(ob.phase(), TradingPhase::Continuous);
+}
+
+#[test]
+fn test_instrument_spec() {
+    let spec = InstrumentSpec::default()
+        .with_tick_size(5)
+        .with_lot_size(10)
+        .with_min_qty(10)
+        .with_max_qty(100)
+        .with_min_notional(10_000);
+    let mut ob = make_order_book(Some(OrderBookOptions {
+        instrument: Some(spec),
+        price_band: Some(PriceBand::new(ReferencePrice::External, 333, PriceBandAction::Cap)),
+        ..Default::default()
+    }));
+    let code = |resp: Result<ExecutionReport>| resp.unwrap_err().code;
+
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 10, 1002, None, None));
+    assert_eq!(code(resp), make_error(ErrorType::PriceNotMultipleOfTick).code);
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 10, 995, None, None));
+    assert_eq!(code(resp), make_error(ErrorType::NotionalBelowMinimum).code);
+    let resp = ob.limit_on_open(LimitOrderOptions::new(Side::Sell, 110, 1000, None, None));
+    assert_eq!(code(resp), make_error(ErrorType::QuantityAboveMaximum).code);
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 20, 1000, None, None)).is_ok());
+
+    let resp = ob.market(MarketOrderOptions::new(Side::Buy, 15));
+    assert_eq!(code(resp), make_error(ErrorType::QuantityNotMultipleOfLot).code);
+    let resp = ob.market_on_open(MarketOrderOptions::new(Side::Buy, 15));
+    assert_eq!(code(resp), make_error(ErrorType::QuantityNotMultipleOfLot).code);
+    assert!(ob.market(MarketOrderOptions::new(Side::Buy, 10)).is_ok());
+
+    // an invalid modify keeps the order
+    let resp = ob.modify(OrderId(0), Some(Price(1001)), None);
+    assert_eq!(code(resp), make_error(ErrorType::PriceNotMultipleOfTick).code);
+    let resp = ob.modify(OrderId(0), None, Some(Quantity(5)));
+    assert_eq!(code(resp), make_error(ErrorType::QuantityNotMultipleOfLot).code);
+    assert_eq!(ob.get_order(OrderId(0)).unwrap().remaining_qty(), Quantity(10));
+
+    // capped prices are rounded inside the band: 1000 + 3.33% = 1033
+    assert!(ob.set_reference_price(Price(1000)).is_ok());
+    let report = ob.limit(LimitOrderOptions::new(Side::Buy, 10, 1100, None, None)).unwrap();
+    assert_eq!(report.price, Price(1030));
+}
+
+#[test]
+fn test_tick_table() {
+    let table = TickTable::new(1).with_tier(1000, 10);
+    let mut ob = make_order_book(Some(OrderBookOptions {
+        instrument: Some(InstrumentSpec::default().with_tick_table(table)),
+        price_band: Some(PriceBand::new(ReferencePrice::External, 500, PriceBandAction::Cap)),
+        ..Default::default()
+    }));
+
+    let resp = ob.limit(LimitOrderOptions::new(Side::Sell, 10, 1005, None, None));
+    assert!(resp.is_err_and(|e| e.code == make_error(ErrorType::PriceNotMultipleOfTick).code));
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 10, 1010, None, None)).is_ok());
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 10, 999, None, None)).is_ok());
+
+    // the band edges 950 and 1050 are rounded with the tick of their own tier
+    assert!(ob.set_reference_price(Price(1000)).is_ok());
+    let report = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 1200, None, None)).unwrap();
+    assert_eq!(report.price, Price(1050));
+    assert!(ob.set_reference_price(Price(1043)).is_ok());
+    let report = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 1200, None, None)).unwrap();
+    // 1043 + 5% = 1095.15
+    assert_eq!(report.price, Price(1090));
+    let report = ob.limit(LimitOrderOptions::new(Side::Sell, 5, 900, None, None)).unwrap();
+    // 1043 - 5% = 990, already on the tick of its tier
+    assert_eq!(report.price, Price(990));
+}
+
+#[derive(Clone, Default)]
+struct EventRecorder(std::sync::Arc<std::sync::Mutex<Vec<OrderBookEvent>>>);
+
+impl OrderBookListener for EventRecorder {
+    fn on_event(&mut self, event: &OrderBookEvent) {
+        self.0.lock().unwrap().push(event.clone());
+    }
+}
+
+impl EventRecorder {
+    fn take(&self) -> Vec<OrderBookEvent> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+
+    /// Takes the order and price level events, without the market data feeds.
+    fn take_order_events(&self) -> Vec<OrderBookEvent> {
+        self.take()
+            .into_iter()
+            .filter(|e| {
+                !matches!(
+                    e,
+                    OrderBookEvent::DepthUpdate(_)
+                        | OrderBookEvent::L3Update(_)
+                        | OrderBookEvent::BboUpdate(_)
+                        | OrderBookEvent::Trade(_)
+                        | OrderBookEvent::CandleClosed(_)
+                        | OrderBookEvent::ConflatedDepth(_)
+                )
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_listener_events() {
+    use OrderBookEvent::*;
+    let recorder = EventRecorder::default();
+    let mut ob = OrderBookBuilder::new("BTCUSD").with_listener(recorder.clone()).build();
+
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 5, 100, None, None)).is_ok());
+    assert_eq!(
+        recorder.take_order_events(),
+        vec![
+            OrderAccepted {
+                id: OrderId(0),
+                order_type: OrderType::Limit,
+                side: Side::Sell,
+                price: Some(Price(100)),
+                quantity: Quantity(5),
+                owner: None,
+            },
+            OrderRested {
+                id: OrderId(0),
+                side: Side::Sell,
+                price: Price(100),
+                quantity: Quantity(5)
+            },
+            PriceLevelAdded { side: Side::Sell, price: Price(100) },
+        ]
+    );
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 5, 101, None, None)).is_ok());
+    recorder.take_order_events();
+
+    // the resting makers are notified of their fills
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 7, 101, None, None)).is_ok());
+    let (id, side, price) = (OrderId(2), Side::Buy, Price(101));
+    assert_eq!(
+        recorder.take_order_events()[1..],
+        [
+            PriceLevelRemoved { side: Side::Sell, price: Price(100) },
+            OrderFilled {
+                id: OrderId(0),
+                side: Side::Sell,
+                price: Price(100),
+                quantity: Quantity(5)
+            },
+            OrderPartiallyFilled {
+                id,
+                side,
+                price: Price(100),
+                quantity: Quantity(5),
+                remaining_qty: Quantity(2),
+            },
+            OrderPartiallyFilled {
+                id: OrderId(1),
+                side: Side::Sell,
+                price,
+                quantity: Quantity(2),
+                remaining_qty: Quantity(3),
+            },
+            OrderFilled { id, side, price, quantity: Quantity(2) },
+        ]
+    );
+
+    let report = ob.modify(OrderId(1), Some(Price(102)), None).unwrap();
+    assert_eq!(
+        recorder.take_order_events().last(),
+        Some(&OrderModified {
+            id: OrderId(1),
+            new_id: report.order_id,
+            price: Price(102),
+            quantity: Quantity(3),
+        })
+    );
+    assert!(ob.cancel(report.order_id).is_ok());
+    assert_eq!(
+        recorder.take_order_events(),
+        vec![
+            PriceLevelRemoved { side: Side::Sell, price: Price(102) },
+            OrderCanceled { id: report.order_id, side: Side::Sell, remaining_qty: Quantity(3) },
+        ]
+    );
+
+    assert!(ob.market(MarketOrderOptions::new(Side::Buy, 1)).is_err());
+    assert_eq!(
+        recorder.take_order_events(),
+        vec![OrderRejected {
+            order_type: OrderType::Market,
+            side: Side::Buy,
+            owner: None,
+            error: make_error(ErrorType::OrderBookEmpty),
+        }]
+    );
+}
+
+#[test]
+fn test_rejected_modify_events() {
+    use OrderBookEvent::*;
+    let recorder = EventRecorder::default();
+    let mut ob = OrderBookBuilder::new("BTCUSD")
+        .with_risk_limits(RiskLimits::default().with_max_order_qty(10))
+        .with_listener(recorder.clone())
+        .build();
+    let owner = Some(OwnerId(1));
+    let options = LimitOrderOptions::new(Side::Sell, 5, 100, None, None).with_owner(OwnerId(1));
+    let id = ob.limit(options).unwrap().order_id;
+    recorder.take();
+
+    // the pre-checks of a modify are reported like the ones of a new order
+    assert!(ob.modify(id, None, Some(Quantity(11))).is_err());
+    assert_eq!(
+        recorder.take_order_events(),
+        vec![OrderRejected {
+            order_type: OrderType::Limit,
+            side: Side::Sell,
+            owner,
+            error: make_error(ErrorType::RiskMaxOrderQty),
+        }]
+    );
+    assert!(ob.get_order(id).is_ok());
+}
+
+#[test]
+fn test_depth_updates() {
+    let recorder = EventRecorder::default();
+    let mut ob = OrderBookBuilder::new("BTCUSD").with_listener(recorder.clone()).build();
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 5, 100, None, None)).is_ok());
+    recorder.take();
+
+    // a consumer bootstraps from a snapshot, then applies the updates
+    let snapshot = ob.depth_snapshot(None);
+    assert_eq!(snapshot.sequence, 1);
+    let mut sequence = snapshot.sequence;
+    let mut bids: BTreeMap<Price, Quantity> = levels(&snapshot.depth.bids).into_iter().collect();
+    let mut asks: BTreeMap<Price, Quantity> = levels(&snapshot.depth.asks).into_iter().collect();
+
+    let ops: [fn(&mut OrderBook); 10] = [
+        |ob| assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 5, 101, None, None)).is_ok()),
+        |ob| assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 3, 101, None, None)).is_ok()),
+        |ob| assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 4, 98, None, None)).is_ok()),
+        |ob| assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 8, 101, None, None)).is_ok()),
+        |ob| assert!(ob.market(MarketOrderOptions::new(Side::Sell, 2)).is_ok()),
+        |ob| assert!(ob.modify(OrderId(3), Some(Price(97)), None).is_ok()),
+        |ob| assert!(ob.cancel(OrderId(2)).is_ok()),
+        |ob| assert!(ob.set_phase(TradingPhase::Auction).is_ok()),
+        |ob| assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 3, 96, None, None)).is_ok()),
+        |ob| assert!(ob.uncross().is_ok()),
+    ];
+    for op in ops {
+        op(&mut ob);
+        for event in recorder.take() {
+            let OrderBookEvent::DepthUpdate(update) = event else { continue };
+            assert_eq!(update.sequence, sequence + 1);
+            sequence = update.sequence;
+            let levels = match update.side {
+                Side::Buy => &mut bids,
+                Side::Sell => &mut asks,
+            };
+            if update.quantity.value() == 0 {
+                levels.remove(&update.price);
+            } else {
+                levels.insert(update.price, update.quantity);
+            }
+        }
+        let depth = ob.depth(None);
+        assert_eq!(asks.clone().into_iter().collect::<Vec<_>>(), levels(&depth.asks));
+        assert_eq!(bids.clone().into_iter().rev().collect::<Vec<_>>(), levels(&depth.bids));
+        assert_eq!(ob.depth_snapshot(None).sequence, sequence);
+    }
+    assert!(sequence > snapshot.sequence);
+    assert_eq!(ob.snapshot().depth_sequence, sequence);
+}
+
+#[test]
+fn test_l3_updates() {
+    let recorder = EventRecorder::default();
+    let mut ob = OrderBookBuilder::new("BTCUSD").with_listener(recorder.clone()).build();
+    let owner = OwnerId(1);
+
+    // the consumer rebuilds the book from the feed alone
+    let mut sequence = 0;
+    let mut orders: HashMap<OrderId, Quantity> = HashMap::new();
+    let mut bids: BTreeMap<Price, VecDeque<OrderId>> = BTreeMap::new();
+    let mut asks: BTreeMap<Price, VecDeque<OrderId>> = BTreeMap::new();
+
+    let ops: [fn(&mut OrderBook); 13] = [
+        |ob| assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 5, 101, None, None)).is_ok()),
+        |ob| {
+            let options = LimitOrderOptions::new(Side::Sell, 5, 100, None, None);
+            assert!(ob.limit(options.with_owner(OwnerId(1))).is_ok())
+        },
+        |ob| assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 3, 101, None, None)).is_ok()),
+        |ob| assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 4, 98, None, None)).is_ok()),
+        |ob| assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 8, 101, None, None)).is_ok()),
+        |ob| assert!(ob.market(MarketOrderOptions::new(Side::Sell, 2)).is_ok()),
+        |ob| assert!(ob.modify(OrderId(3), Some(Price(97)), None).is_ok()),
+        // the replacement of the order 6 is filled and never rests
+        |ob| assert!(ob.modify(OrderId(6), Some(Price(101)), None).is_ok()),
+        |ob| assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 4, 99, None, None)).is_ok()),
+        |ob| assert!(ob.cancel(OrderId(2)).is_ok()),
+        |ob| assert!(ob.set_phase(TradingPhase::Auction).is_ok()),
+        |ob| assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 1, 99, None, None)).is_ok()),
+        |ob| assert_eq!(ob.uncross().unwrap().quantity, Quantity(1)),
+    ];
+    for op in ops {
+        op(&mut ob);
+        for event in recorder.take() {
+            let OrderBookEvent::L3Update(update) = event else { continue };
+            assert_eq!(update.sequence, sequence + 1);
+            sequence = update.sequence;
+            let added = match update.message {
+                L3Message::Add { id, side, price, quantity, owner } => {
+                    assert_eq!(owner, None);
+                    orders.insert(id, quantity);
+                    Some((side, price, id))
+                }
+                L3Message::Execute { id, quantity, .. }
+                | L3Message::Reduce { id, quantity, .. } => {
+                    let open = orders.get_mut(&id).unwrap();
+                    *open = open.sub(quantity);
+                    if open.value() == 0 {
+                        orders.remove(&id);
+                    }
+                    None
+                }
+                L3Message::Delete { id, quantity, .. } => {
+                    assert_eq!(orders.remove(&id), Some(quantity));
+                    None
+                }
+                L3Message::Replace { id, new_id, side, price, quantity } => {
+                    orders.remove(&id);
+                    orders.insert(new_id, quantity);
+                    Some((side, price, new_id))
+                }
+            };
+            if let Some((side, price, id)) = added {
+                let levels = if side == Side::Buy { &mut bids } else { &mut asks };
+                levels.entry(price).or_default().push_back(id);
+            }
+        }
+        bids.values_mut()
+            .chain(asks.values_mut())
+            .for_each(|q| q.retain(|id| orders.contains_key(id)));
+        bids.retain(|_, q| !q.is_empty());
+        asks.retain(|_, q| !q.is_empty());
+
+        let snapshot = ob.snapshot();
+        assert_eq!(bids, snapshot.bids);
+        assert_eq!(asks, snapshot.asks);
+        let open: HashMap<OrderId, Quantity> =
+            snapshot.orders.values().map(|o| (o.id, o.remaining_qty())).collect();
+        assert_eq!(orders, open);
+    }
+    assert_eq!(ob.snapshot().l3_sequence, sequence);
+
+    // owners are only disclosed with order attribution
+    let mut ob = OrderBookBuilder::new("BTCUSD")
+        .with_order_attribution(true)
+        .with_listener(recorder.clone())
+        .build();
+    let options = LimitOrderOptions::new(Side::Sell, 5, 100, None, None).with_owner(owner);
+    assert!(ob.limit(options).is_ok());
+    assert!(recorder.take().contains(&OrderBookEvent::L3Update(L3Update {
+        sequence: 1,
+        message: L3Message::Add {
+            id: OrderId(0),
+            side: Side::Sell,
+            price: Price(100),
+            quantity: Quantity(5),
+            owner: Some(owner),
+        },
+    })));
+}
+
+#[test]
+fn test_bbo_updates() {
+    let recorder = EventRecorder::default();
+    let mut ob = OrderBookBuilder::new("BTCUSD").with_listener(recorder.clone()).build();
+    let bbo_updates = |recorder: &EventRecorder| -> Vec<BboUpdate> {
+        recorder
+            .take()
+            .into_iter()
+            .filter_map(|e| match e {
+                OrderBookEvent::BboUpdate(update) => Some(update),
+                _ => None,
+            })
+            .collect()
+    };
+    let top = |bid: Option<(u64, u64)>, ask: Option<(u64, u64)>| TopOfBook {
+        bid: bid.map(|(p, q)| (Price(p), Quantity(q))),
+        ask: ask.map(|(p, q)| (Price(p), Quantity(q))),
+    };
+
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 5, 100, None, None)).is_ok());
+    assert_eq!(
+        bbo_updates(&recorder),
+        vec![BboUpdate {
+            sequence: 1,
+            previous: top(None, None),
+            current: top(None, Some((100, 5)))
+        }]
+    );
+
+    // behind the best ask: no change
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 5, 101, None, None)).is_ok());
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 3, 90, None, None)).is_ok());
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 3, 80, None, None)).is_ok());
+    let updates = bbo_updates(&recorder);
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].sequence, 2);
+    assert_eq!(updates[0].current, top(Some((90, 3)), Some((100, 5))));
+    assert_eq!(ob.top_of_book(), top(Some((90, 3)), Some((100, 5))));
+
+    // a sweep emits a single update with the final state
+    assert!(ob.market(MarketOrderOptions::new(Side::Buy, 7)).is_ok());
+    assert_eq!(
+        bbo_updates(&recorder),
+        vec![BboUpdate {
+            sequence: 3,
+            previous: top(Some((90, 3)), Some((100, 5))),
+            current: top(Some((90, 3)), Some((101, 3))),
+        }]
+    );
+
+    // size changes at the top are reported, a modify is a single operation
+    assert!(ob.modify(OrderId(2), None, Some(Quantity(4))).is_ok());
+    let updates = bbo_updates(&recorder);
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].previous.bid, Some((Price(90), Quantity(3))));
+    assert_eq!(updates[0].current.bid, Some((Price(90), Quantity(4))));
+
+    // rejected orders and changes away from the top are silent
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 0, 90, None, None)).is_err());
+    assert!(ob.cancel(OrderId(3)).is_ok());
+    assert!(bbo_updates(&recorder).is_empty());
+
+    assert!(ob.cancel(OrderId(1)).is_ok());
+    let updates = bbo_updates(&recorder);
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].sequence, 5);
+    assert_eq!(updates[0].current, top(Some((90, 4)), None));
+    assert_eq!(ob.snapshot().bbo_sequence, 5);
+}
+
+#[test]
+fn test_trade_tape() {
+    let recorder = EventRecorder::default();
+    let mut ob = OrderBookBuilder::new("BTCUSD")
+        .with_trade_history(3)
+        .with_listener(recorder.clone())
+        .build();
+    ob.set_clock(Some(1_000));
+
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 2, 100, None, None)).is_ok());
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 2, 101, None, None)).is_ok());
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 3, 101, None, None)).is_ok());
+    assert_eq!(
+        ob.recent_trades(None),
+        vec![
+            Trade {
+                id: TradeId(1),
+                maker_order_id: OrderId(1),
+                taker_order_id: OrderId(2),
+                aggressor: Some(Side::Buy),
+                price: Price(101),
+                quantity: Quantity(1),
+                ts: 1_000,
+            },
+            Trade {
+                id: TradeId(0),
+                maker_order_id: OrderId(0),
+                taker_order_id: OrderId(2),
+                aggressor: Some(Side::Buy),
+                price: Price(100),
+                quantity: Quantity(2),
+                ts: 1_000,
+            },
+        ]
+    );
+    // every trade is published, oldest first
+    let published: Vec<Trade> = recorder
+        .take()
+        .into_iter()
+        .filter_map(|e| match e {
+            OrderBookEvent::Trade(trade) => Some(trade),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(published, ob.recent_trades(None).into_iter().rev().collect::<Vec<_>>());
+
+    // the buffer keeps the last 3 trades
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 2, 99, None, None)).is_ok());
+    ob.set_clock(Some(2_000));
+    assert!(ob.market(MarketOrderOptions::new(Side::Sell, 2)).is_ok());
+    let recent = ob.recent_trades(None);
+    assert_eq!(recent.len(), 3);
+    assert_eq!(recent[0].id, TradeId(2));
+    assert_eq!(recent[0].maker_order_id, OrderId(3));
+    assert_eq!(recent[0].taker_order_id, OrderId(4));
+    assert_eq!(recent[0].aggressor, Some(Side::Sell));
+    assert_eq!(recent[0].ts, 2_000);
+    assert_eq!(ob.recent_trades(Some(1)), vec![recent[0]]);
+    assert_eq!(ob.trades_since(TradeId(1)), vec![recent[0]]);
+
+    // the trade IDs and the recent trades survive a snapshot
+    let restored = OrderBookBuilder::new("BTCUSD").with_snapshot(ob.snapshot()).build();
+    assert_eq!(restored.recent_trades(None), recent);
+    assert_eq!(restored.next_trade_id, TradeId(3));
+
+    // auction trades have no aggressor
+    assert!(ob.set_phase(TradingPhase::Auction).is_ok());
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 1, 102, None, None)).is_ok());
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 1, 102, None, None)).is_ok());
+    assert!(ob.uncross().is_ok());
+    let trade = ob.recent_trades(Some(1))[0];
+    assert_eq!(trade.id, TradeId(3));
+    assert_eq!(trade.aggressor, None);
+    assert_eq!((trade.maker_order_id, trade.taker_order_id), (OrderId(1), OrderId(5)));
+}
+
+#[test]
+fn test_candles() {
+    let recorder = EventRecorder::default();
+    let mut ob = OrderBookBuilder::new("BTCUSD")
+        .with_candles(&[CandleInterval::ONE_SECOND, CandleInterval::ONE_MINUTE])
+        .with_listener(recorder.clone())
+        .build();
+    let closed_candles = |recorder: &EventRecorder| -> Vec<Candle> {
+        recorder
+            .take()
+            .into_iter()
+            .filter_map(|e| match e {
+                OrderBookEvent::CandleClosed(candle) => Some(candle),
+                _ => None,
+            })
+            .collect()
+    };
+
+    ob.set_clock(Some(10_200));
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 2, 100, None, None)).is_ok());
+    assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 3, 102, None, None)).is_ok());
+    // a single taker sweeping two levels makes two trades
+    assert!(ob.market(MarketOrderOptions::new(Side::Buy, 4)).is_ok());
+    ob.set_clock(Some(10_900));
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 2, 99, None, None)).is_ok());
+    assert!(ob.market(MarketOrderOptions::new(Side::Sell, 1)).is_ok());
+    assert!(closed_candles(&recorder).is_empty());
+
+    let candle = ob.candle(CandleInterval::ONE_SECOND).unwrap();
+    assert_eq!(candle.start, 10_000);
+    assert_eq!((candle.open, candle.high, candle.low), (Price(100), Price(102), Price(99)));
+    assert_eq!(candle.close, Price(99));
+    assert_eq!(candle.volume, Quantity(5));
+    assert_eq!(candle.notional, 200 + 204 + 99);
+    assert_eq!(candle.vwap, Price(100));
+    assert_eq!(candle.trades, 3);
+    assert_eq!(
+        ob.candle(CandleInterval::ONE_MINUTE),
+        Some(Candle { interval: CandleInterval::ONE_MINUTE, start: 0, ..candle })
+    );
+    assert_eq!(ob.candle(CandleInterval::ONE_HOUR), None);
+
+    // the open candles survive a snapshot
+    let restored = OrderBookBuilder::new("BTCUSD")
+        .with_candles(&[CandleInterval::ONE_SECOND])
+        .with_snapshot(ob.snapshot())
+        .build();
+    assert_eq!(restored.candle(CandleInterval::ONE_SECOND), Some(candle));
+    assert_eq!(restored.candle(CandleInterval::ONE_MINUTE), None);
+
+    // moving the clock closes the one second candle
+    ob.set_clock(Some(11_000));
+    assert_eq!(closed_candles(&recorder), vec![candle]);
+    assert_eq!(ob.candle(CandleInterval::ONE_SECOND), None);
+
+    // the next trade opens a new candle, the minute candle is still open
+    ob.set_clock(Some(30_000));
+    assert!(ob.market(MarketOrderOptions::new(Side::Sell, 1)).is_ok());
+    assert!(closed_candles(&recorder).is_empty());
+    assert_eq!(ob.candle(CandleInterval::ONE_SECOND).unwrap().start, 30_000);
+    assert_eq!(ob.candle(CandleInterval::ONE_MINUTE).unwrap().trades, 4);
+
+    ob.set_clock(Some(61_000));
+    let closed = closed_candles(&recorder);
+    assert_eq!(closed.len(), 2);
+    assert_eq!(closed[1].interval, CandleInterval::ONE_MINUTE);
+    assert_eq!(closed[1].volume, Quantity(6));
+}
+
+#[test]
+fn test_depth_levels() {
+    let mut ob = OrderBook::new("BTCUSD", OrderBookOptions::default());
+    for (side, quantity, price) in [
+        (Side::Sell, 2, 101),
+        (Side::Sell, 3, 101),
+        (Side::Sell, 1, 102),
+        (Side::Sell, 4, 103),
+        (Side::Buy, 5, 99),
+        (Side::Buy, 1, 98),
+        (Side::Buy, 2, 98),
+    ] {
+        assert!(ob.limit(LimitOrderOptions::new(side, quantity, price, None, None)).is_ok());
+    }
+
+    let depth = ob.depth(Some(2));
+    assert_eq!(
+        depth.asks,
+        vec![
+            DepthLevel {
+                price: Price(101),
+                quantity: Quantity(5),
+                order_count: 2,
+                cumulative_qty: Quantity(5),
+                cumulative_notional: 505,
+            },
+            DepthLevel {
+                price: Price(102),
+                quantity: Quantity(1),
+                order_count: 1,
+                cumulative_qty: Quantity(6),
+                cumulative_notional: 607,
+            },
+        ]
+    );
+    assert_eq!(
+        depth.bids,
+        vec![
+            DepthLevel {
+                price: Price(99),
+                quantity: Quantity(5),
+                order_count: 1,
+                cumulative_qty: Quantity(5),
+                cumulative_notional: 495,
+            },
+            DepthLevel {
+                price: Price(98),
+                quantity: Quantity(3),
+                order_count: 2,
+                cumulative_qty: Quantity(8),
+                cumulative_notional: 789,
+            },
+        ]
+    );
+
+    assert_eq!(ob.depth(Some(1)).asks.len(), 1);
+    assert!(ob.depth(Some(0)).bids.is_empty());
+    let depth = ob.depth(None);
+    assert_eq!(depth.asks.len(), 3);
+    assert_eq!(depth.asks[2].cumulative_qty, Quantity(10));
+}
+
+#[test]
+fn test_grouped_depth() {
+    let mut ob = OrderBook::new("BTCUSD", OrderBookOptions::default());
+    for (side, quantity, price) in [
+        (Side::Sell, 1, 1001),
+        (Side::Sell, 2, 1010),
+        (Side::Sell, 3, 1011),
+        (Side::Sell, 4, 1025),
+        (Side::Buy, 5, 999),
+        (Side::Buy, 1, 990),
+        (Side::Buy, 2, 989),
+    ] {
+        assert!(ob.limit(LimitOrderOptions::new(side, quantity, price, None, None)).is_ok());
+    }
+
+    let depth = ob.grouped_depth(DepthGrouping::Step(Price(10)), None).unwrap();
+    assert_eq!(
+        levels(&depth.asks),
+        vec![(Price(1010), Quantity(3)), (Price(1020), Quantity(3)), (Price(1030), Quantity(4))]
+    );
+    assert_eq!(levels(&depth.bids), vec![(Price(990), Quantity(6)), (Price(980), Quantity(2))]);
+    assert_eq!(
+        depth.bids[0],
+        DepthLevel {
+            price: Price(990),
+            quantity: Quantity(6),
+            order_count: 2,
+            cumulative_qty: Quantity(6),
+            cumulative_notional: 5 * 999 + 990,
+        }
+    );
+    assert_eq!(depth.asks[2].cumulative_qty, Quantity(10));
+
+    // the limit applies to the buckets
+    let depth = ob.grouped_depth(DepthGrouping::Step(Price(10)), Some(1)).unwrap();
+    assert_eq!(levels(&depth.asks), vec![(Price(1010), Quantity(3))]);
+    assert_eq!(levels(&depth.bids), vec![(Price(990), Quantity(6))]);
+
+    // a step of one tick is the plain depth
+    assert_eq!(ob.grouped_depth(DepthGrouping::Ticks(1), None).unwrap(), ob.depth(None));
+
+    // tick multiples follow the tick size of the instrument
+    let mut ob = OrderBookBuilder::new("BTCUSD")
+        .with_instrument(
+            InstrumentSpec::default().with_tick_table(TickTable::new(1).with_tier(1000, 5)),
+        )
+        .build();
+    for (side, quantity, price) in
+        [(Side::Sell, 1, 1005), (Side::Sell, 1, 1015), (Side::Buy, 1, 999), (Side::Buy, 1, 991)]
+    {
+        assert!(ob.limit(LimitOrderOptions::new(side, quantity, price, None, None)).is_ok());
+    }
+    let depth = ob.grouped_depth(DepthGrouping::Ticks(4), None).unwrap();
+    assert_eq!(levels(&depth.asks), vec![(Price(1020), Quantity(2))]);
+    assert_eq!(levels(&depth.bids), vec![(Price(996), Quantity(1)), (Price(988), Quantity(1))]);
+
+    for grouping in [DepthGrouping::Ticks(0), DepthGrouping::Step(Price(0))] {
+        assert_eq!(
+            ob.grouped_depth(grouping, None).unwrap_err().code,
+            make_error(ErrorType::InvalidDepthGrouping).code
+        );
+    }
+}
+
+#[test]
+fn test_snapshot_diff() {
+    let mut ob = OrderBook::new("BTCUSD", OrderBookOptions::default());
+    for (side, quantity, price) in
+        [(Side::Sell, 2, 101), (Side::Sell, 3, 101), (Side::Sell, 1, 102), (Side::Buy, 5, 99)]
+    {
+        assert!(ob.limit(LimitOrderOptions::new(side, quantity, price, None, None)).is_ok());
+    }
+    let primary = ob.snapshot();
+    assert!(primary.diff(&primary.clone()).is_empty());
+
+    // the first order of 101 is partially filled, 102 is canceled and 98 is added
+    assert!(ob.market(MarketOrderOptions::new(Side::Buy, 1)).is_ok());
+    assert!(ob.cancel(OrderId(2)).is_ok());
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 1, 98, None, None)).is_ok());
+    let standby = ob.snapshot();
+    let diff = primary.diff(&standby);
+    assert_eq!(diff.orders_added, vec![OrderId(5)]);
+    assert_eq!(diff.orders_removed, vec![OrderId(2)]);
+    assert_eq!(diff.orders_changed, vec![OrderId(0)]);
+    assert_eq!(diff.levels_added, vec![(Side::Buy, Price(98))]);
+    assert_eq!(diff.levels_removed, vec![(Side::Sell, Price(102))]);
+    assert!(diff.queues_changed.is_empty());
+
+    // the reverse diff swaps the additions and the removals
+    let reverse = standby.diff(&primary);
+    assert_eq!(reverse.orders_added, diff.orders_removed);
+    assert_eq!(reverse.orders_removed, diff.orders_added);
+    assert_eq!(reverse.levels_added, diff.levels_removed);
+
+    // same orders in a different time priority
+    let mut reordered = primary.clone();
+    reordered.asks.get_mut(&Price(101)).unwrap().swap(0, 1);
+    let diff = primary.diff(&reordered);
+    assert_eq!(diff.queues_changed, vec![(Side::Sell, Price(101))]);
+    assert!(diff.orders_changed.is_empty());
+    assert!(!diff.is_empty());
+}
+
+#[test]
+fn test_conflated_depth() {
+    let recorder = EventRecorder::default();
+    let policy = ConflationPolicy::default().with_interval(100).with_max_updates(6);
+    let mut ob = OrderBookBuilder::new("BTCUSD")
+        .with_conflation(policy)
+        .with_listener(recorder.clone())
+        .build();
+    let batches = |recorder: &EventRecorder| -> Vec<ConflatedDepthUpdate> {
+        recorder
+            .take()
+            .into_iter()
+            .filter_map(|e| match e {
+                OrderBookEvent::ConflatedDepth(batch) => Some(batch),
+                _ => None,
+            })
+            .collect()
+    };
+
+    ob.set_clock(Some(1_000));
+    let snapshot = ob.depth_snapshot(None);
+    let mut asks: BTreeMap<Price, Quantity> = BTreeMap::new();
+    let mut bids: BTreeMap<Price, Quantity> = BTreeMap::new();
+    let mut apply = |batch: &ConflatedDepthUpdate| {
+        for update in &batch.updates {
+            let levels = if update.side == Side::Buy { &mut bids } else { &mut asks };
+            if update.quantity.value() == 0 {
+                levels.remove(&update.price);
+            } else {
+                levels.insert(update.price, update.quantity);
+            }
+        }
+    };
+    assert_eq!(snapshot.sequence, 0);
+
+    // five updates on two levels stay pending until the interval elapses
+    for quantity in [1, 2, 3] {
+        assert!(ob.limit(LimitOrderOptions::new(Side::Sell, quantity, 101, None, None)).is_ok());
+    }
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 4, 99, None, None)).is_ok());
+    assert!(ob.cancel(OrderId(0)).is_ok());
+    ob.set_clock(Some(1_099));
+    assert!(batches(&recorder).is_empty());
+    ob.set_clock(Some(1_100));
+    let batch = batches(&recorder);
+    assert_eq!(
+        batch,
+        vec![ConflatedDepthUpdate {
+            sequence: 1,
+            depth_sequence: 5,
+            updates: vec![
+                DepthUpdate {
+                    sequence: 4,
+                    side: Side::Buy,
+                    price: Price(99),
+                    quantity: Quantity(4)
+                },
+                DepthUpdate {
+                    sequence: 5,
+                    side: Side::Sell,
+                    price: Price(101),
+                    quantity: Quantity(5)
+                },
+            ],
+        }]
+    );
+    apply(&batch[0]);
+
+    // a sweep reaching the update count is flushed at the end of the operation
+    for price in [102, 103, 104, 105] {
+        assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 1, price, None, None)).is_ok());
+    }
+    assert!(batches(&recorder).is_empty());
+    assert!(ob.market(MarketOrderOptions::new(Side::Buy, 7)).is_ok());
+    let batch = batches(&recorder);
+    assert_eq!(batch.len(), 1);
+    assert_eq!(batch[0].sequence, 2);
+    assert_eq!(batch[0].depth_sequence, ob.depth_snapshot(None).sequence);
+    apply(&batch[0]);
+
+    // a manual flush publishes what is pending
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 1, 98, None, None)).is_ok());
+    ob.flush_conflated();
+    let batch = batches(&recorder);
+    assert_eq!(batch.len(), 1);
+    apply(&batch[0]);
+    ob.flush_conflated();
+    assert!(batches(&recorder).is_empty());
+
+    let depth = ob.depth(None);
+    assert_eq!(asks.into_iter().collect::<Vec<_>>(), levels(&depth.asks));
+    assert_eq!(bids.into_iter().rev().collect::<Vec<_>>(), levels(&depth.bids));
+    assert_eq!(ob.snapshot().conflated_sequence, 3);
+}
+
+#[test]
+fn test_auction_market_order_remainder_market_data() {
+    let recorder = EventRecorder::default();
+    let mut ob = OrderBookBuilder::new("BTCUSD").with_listener(recorder.clone()).build();
+    assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 2, 1000, None, None)).is_ok());
+    assert!(ob.limit_on_open(LimitOrderOptions::new(Side::Sell, 5, 1000, None, None)).is_ok());
+    let moo_id = ob.market_on_open(MarketOrderOptions::new(Side::Buy, 10)).unwrap().order_id;
+    assert!(ob.set_phase(TradingPhase::Closed).is_ok());
+    assert!(ob.set_phase(TradingPhase::PreOpen).is_ok());
+    recorder.take();
+
+    // the unfilled market-on-open order never rested at the auction price
+    let report = ob.uncross().unwrap();
+    assert_eq!(report.price, Some(Price(1000)));
+    assert_eq!(report.canceled, vec![moo_id]);
+    let events = recorder.take();
+    assert!(!events.iter().any(|e| matches!(
+        e,
+        OrderBookEvent::L3Update(L3Update { message: L3Message::Delete { id, .. }, .. })
+            if *id == moo_id
+    )));
+    let bid_updates: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            OrderBookEvent::DepthUpdate(u) if u.side == Side::Buy => Some(u),
+            _ => None,
+        })
+        .collect();
+    assert!(bid_updates.is_empty());
+    assert_eq!(levels(&ob.depth(None).bids), vec![(Price(1000), Quantity(2))]);
+}
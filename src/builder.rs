@@ -12,7 +12,14 @@
 //!     .build();
 //! ```
 use crate::{
+    candles::CandleInterval,
+    conflation::ConflationPolicy,
+    events::OrderBookListener,
+    fees::FeeSchedule,
+    instrument::InstrumentSpec,
     journal::{JournalLog, Snapshot},
+    risk::{PriceBand, RiskLimits, VolatilityInterruption},
+    throttle::ThrottleLimits,
     OrderBook, OrderBookOptions,
 };
 
@@ -23,6 +30,7 @@ use crate::{
 pub struct OrderBookBuilder {
     symbol: String,
     options: OrderBookOptions,
+    listeners: Vec<Box<dyn OrderBookListener>>,
 }
 
 impl OrderBookBuilder {
@@ -31,7 +39,7 @@ impl OrderBookBuilder {
     /// # Parameters
     /// - `symbol`: The market symbol (e.g., `"BTCUSD"`)
     pub fn new(symbol: impl Into<String>) -> Self {
-        Self { symbol: symbol.into(), options: OrderBookOptions::default() }
+        Self { symbol: symbol.into(), options: OrderBookOptions::default(), listeners: Vec::new() }
     }
 
     /// Sets all options in bulk via an [`OrderBookOptions`] struct.
@@ -82,6 +90,138 @@ impl OrderBookBuilder {
         self
     }
 
+    /// Enables pre-trade risk checks.
+    ///
+    /// Every order is checked against the given limits before being validated
+    /// and rejected with a dedicated error code if any limit is exceeded.
+    ///
+    /// # Parameters
+    /// - `limits`: The [`RiskLimits`] to enforce
+    pub fn with_risk_limits(mut self, limits: RiskLimits) -> Self {
+        self.options.risk_limits = Some(limits);
+        self
+    }
+
+    /// Enables a dynamic price band (collar) around a reference price.
+    ///
+    /// # Parameters
+    /// - `band`: The [`PriceBand`] to enforce
+    pub fn with_price_band(mut self, band: PriceBand) -> Self {
+        self.options.price_band = Some(band);
+        self
+    }
+
+    /// Sets the trading rules (tick size, lot size and size limits) of the
+    /// instrument.
+    ///
+    /// # Parameters
+    /// - `spec`: The [`InstrumentSpec`] every order is validated against
+    pub fn with_instrument(mut self, spec: InstrumentSpec) -> Self {
+        self.options.instrument = Some(spec);
+        self
+    }
+
+    /// Enables volatility interruptions (circuit breaker).
+    ///
+    /// # Parameters
+    /// - `interruption`: The [`VolatilityInterruption`] to enforce
+    pub fn with_volatility_interruption(mut self, interruption: VolatilityInterruption) -> Self {
+        self.options.volatility_interruption = Some(interruption);
+        self
+    }
+
+    /// Registers a listener notified of every event of the order book.
+    ///
+    /// Listeners are attached once the snapshot and the journal logs of the
+    /// builder, if any, have been applied, so they only receive the events of
+    /// the subsequent operations.
+    ///
+    /// # Parameters
+    /// - `listener`: The [`OrderBookListener`] to notify
+    pub fn with_listener(mut self, listener: impl OrderBookListener + 'static) -> Self {
+        self.listeners.push(Box::new(listener));
+        self
+    }
+
+    /// Discloses the owner of every order on the market-by-order feed.
+    ///
+    /// # Parameters
+    /// - `enabled`: `true` to disclose owners, `false` to mask them
+    pub fn with_order_attribution(mut self, enabled: bool) -> Self {
+        self.options.order_attribution = enabled;
+        self
+    }
+
+    /// Enables or disables the balance ledger.
+    ///
+    /// When enabled, every order must belong to an owner with enough free balance:
+    /// funds are reserved when a limit order is placed, settled on every fill and
+    /// released on cancel.
+    ///
+    /// # Parameters
+    /// - `enabled`: `true` to enable accounts
+    pub fn with_accounts(mut self, enabled: bool) -> Self {
+        self.options.accounts = enabled;
+        self
+    }
+
+    /// Enables or disables position and PnL tracking.
+    ///
+    /// When enabled, every fill updates the net position, average entry price
+    /// and realized PnL of both the maker and the taker owners.
+    ///
+    /// # Parameters
+    /// - `enabled`: `true` to enable position tracking
+    pub fn with_positions(mut self, enabled: bool) -> Self {
+        self.options.positions = enabled;
+        self
+    }
+
+    /// Sets the maker/taker fee schedule charged on every fill.
+    ///
+    /// # Parameters
+    /// - `schedule`: The [`FeeSchedule`] to apply
+    pub fn with_fees(mut self, schedule: FeeSchedule) -> Self {
+        self.options.fees = Some(schedule);
+        self
+    }
+
+    /// Sets the per-owner message rate and order-to-trade ratio limits.
+    ///
+    /// # Parameters
+    /// - `limits`: The [`ThrottleLimits`] to enforce
+    pub fn with_throttle(mut self, limits: ThrottleLimits) -> Self {
+        self.options.throttle = Some(limits);
+        self
+    }
+
+    /// Enables OHLCV candle aggregation for every interval of `intervals`.
+    ///
+    /// # Parameters
+    /// - `intervals`: The [`CandleInterval`]s to aggregate
+    pub fn with_candles(mut self, intervals: &[CandleInterval]) -> Self {
+        self.options.candles = intervals.to_vec();
+        self
+    }
+
+    /// Enables the conflated level-2 feed, flushed according to `policy`.
+    ///
+    /// # Parameters
+    /// - `policy`: The [`ConflationPolicy`] of the batches
+    pub fn with_conflation(mut self, policy: ConflationPolicy) -> Self {
+        self.options.conflation = Some(policy);
+        self
+    }
+
+    /// Sets the number of recent trades kept by the book, 1 000 by default.
+    ///
+    /// # Parameters
+    /// - `capacity`: Maximum number of recent trades, `0` to keep none
+    pub fn with_trade_history(mut self, capacity: usize) -> Self {
+        self.options.trade_history = Some(capacity);
+        self
+    }
+
     /// Builds and returns a fully configured [`OrderBook`] instance.
     ///
     /// # Returns
@@ -95,6 +235,9 @@ impl OrderBookBuilder {
         if let Some(logs) = self.options.replay_logs {
             ob.replay_logs(logs).unwrap(); // panic if logs are invalid
         }
+        ob.listeners = self.listeners;
+        // The top of book is only tracked while listeners are attached
+        ob.last_bbo = ob.top_of_book();
 
         ob
     }
@@ -102,13 +245,14 @@ impl OrderBookBuilder {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::{BTreeMap, HashMap};
+    use std::collections::{BTreeMap, HashMap, VecDeque};
 
     use crate::{
         enums::{JournalOp, OrderOptions},
         order::{OrderId, Price, Quantity},
         utils::current_timestamp_millis,
-        LimitOrderOptions, MarketOrderOptions, Side,
+        InterruptionPhase, InterruptionRemainder, LimitOrderOptions, MarketOrderOptions,
+        PriceBandAction, ReferencePrice, Side, TradeId, TradingPhase,
     };
 
     use super::*;
@@ -148,6 +292,21 @@ mod tests {
             last_op: 42,
             next_order_id: OrderId(100),
             ts: current_timestamp_millis(),
+            last_trade_price: None,
+            reference_price: None,
+            accounts: None,
+            positions: None,
+            fee_volumes: None,
+            throttle: None,
+            phase: TradingPhase::Halted,
+            auction_orders: HashMap::new(),
+            depth_sequence: 0,
+            l3_sequence: 0,
+            bbo_sequence: 0,
+            next_trade_id: TradeId(0),
+            trades: VecDeque::new(),
+            candles: None,
+            conflated_sequence: 0,
         };
 
         let book = OrderBookBuilder::new("BTCUSD").with_snapshot(snap).build();
@@ -155,6 +314,7 @@ mod tests {
         assert_eq!(book.last_op, 42);
         assert_eq!(book.next_order_id, OrderId(100));
         assert_eq!(book.orders.len(), 0);
+        assert_eq!(book.phase(), TradingPhase::Halted);
     }
 
     #[test]
@@ -171,6 +331,9 @@ mod tests {
                     side: Side::Sell,
                     post_only: None,
                     time_in_force: None,
+                    owner: None,
+                    session: None,
+                    persist: false,
                 }),
             },
             JournalLog {
@@ -183,6 +346,9 @@ mod tests {
                     side: Side::Buy,
                     post_only: None,
                     time_in_force: None,
+                    owner: None,
+                    session: None,
+                    persist: false,
                 }),
             },
             JournalLog {
@@ -192,6 +358,7 @@ mod tests {
                 o: OrderOptions::Market(MarketOrderOptions {
                     quantity: Quantity(5),
                     side: Side::Buy,
+                    owner: None,
                 }),
             },
         ];
@@ -206,4 +373,176 @@ mod tests {
         assert_eq!(ob.get_order(OrderId(0)).unwrap().remaining_qty(), Quantity(5));
         assert_eq!(ob.get_order(OrderId(1)).unwrap().remaining_qty(), Quantity(10));
     }
+
+    #[test]
+    fn test_builder_with_listener_and_replay_logs() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::{BboUpdate, OrderBookEvent, TopOfBook};
+
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<Mutex<Vec<OrderBookEvent>>>);
+        impl OrderBookListener for Recorder {
+            fn on_event(&mut self, event: &OrderBookEvent) {
+                self.0.lock().unwrap().push(event.clone());
+            }
+        }
+        let bbo_updates = |recorder: &Recorder| -> Vec<BboUpdate> {
+            let events = std::mem::take(&mut *recorder.0.lock().unwrap());
+            events
+                .into_iter()
+                .filter_map(|e| match e {
+                    OrderBookEvent::BboUpdate(update) => Some(update),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let mut source = OrderBookBuilder::new("BTCUSD").with_journaling(true).build();
+        let logs = vec![
+            source
+                .limit(LimitOrderOptions::new(Side::Sell, 5, 1100, None, None))
+                .unwrap()
+                .log
+                .unwrap(),
+            source
+                .limit(LimitOrderOptions::new(Side::Buy, 5, 1000, None, None))
+                .unwrap()
+                .log
+                .unwrap(),
+        ];
+        let recorder = Recorder::default();
+        let mut ob = OrderBookBuilder::new("BTCUSD")
+            .with_replay_logs(logs)
+            .with_listener(recorder.clone())
+            .build();
+        assert!(bbo_updates(&recorder).is_empty());
+
+        // an operation leaving the top of book unchanged publishes nothing
+        assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 1, 900, None, None)).is_ok());
+        assert!(bbo_updates(&recorder).is_empty());
+
+        // a change is published against the replayed top of book
+        assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 1, 1050, None, None)).is_ok());
+        let top = TopOfBook {
+            bid: Some((Price(1000), Quantity(5))),
+            ask: Some((Price(1100), Quantity(5))),
+        };
+        assert_eq!(
+            bbo_updates(&recorder),
+            vec![BboUpdate {
+                sequence: 1,
+                previous: top,
+                current: TopOfBook { bid: Some((Price(1050), Quantity(1))), ..top },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_builder_with_risk_limits() {
+        let limits = RiskLimits::default().with_max_order_qty(10);
+        let ob = OrderBookBuilder::new("BTCUSD").with_risk_limits(limits).build();
+
+        assert_eq!(ob.risk_limits, Some(limits));
+    }
+
+    #[test]
+    fn test_builder_with_price_band() {
+        let band = PriceBand::new(ReferencePrice::Mid, 100, PriceBandAction::Cap);
+        let ob = OrderBookBuilder::new("BTCUSD").with_price_band(band).build();
+
+        assert_eq!(ob.price_band, Some(band));
+    }
+
+    #[test]
+    fn test_builder_with_instrument() {
+        let spec = InstrumentSpec::default().with_tick_size(5).with_lot_size(10);
+        let ob = OrderBookBuilder::new("BTCUSD").with_instrument(spec.clone()).build();
+
+        assert_eq!(ob.instrument, Some(spec));
+    }
+
+    #[test]
+    fn test_builder_with_volatility_interruption() {
+        let vi =
+            VolatilityInterruption::new(InterruptionPhase::Auction, InterruptionRemainder::Rest)
+                .with_dynamic_bps(500);
+        let ob = OrderBookBuilder::new("BTCUSD").with_volatility_interruption(vi).build();
+
+        assert_eq!(ob.volatility_interruption, Some(vi));
+    }
+
+    #[test]
+    fn test_builder_with_order_attribution() {
+        let ob = OrderBookBuilder::new("BTCUSD").with_order_attribution(true).build();
+        assert!(ob.order_attribution);
+        assert!(!OrderBookBuilder::new("BTCUSD").build().order_attribution);
+    }
+
+    #[test]
+    fn test_builder_with_accounts() {
+        let ob = OrderBookBuilder::new("BTCUSD").build();
+        assert!(ob.accounts.is_none());
+
+        let ob = OrderBookBuilder::new("BTCUSD").with_accounts(true).build();
+        assert!(ob.accounts.is_some());
+    }
+
+    #[test]
+    fn test_builder_with_positions() {
+        let ob = OrderBookBuilder::new("BTCUSD").build();
+        assert!(ob.positions.is_none());
+
+        let ob = OrderBookBuilder::new("BTCUSD").with_positions(true).build();
+        assert!(ob.positions.is_some());
+    }
+
+    #[test]
+    fn test_builder_with_fees() {
+        let schedule = FeeSchedule::new(-1, 5);
+        let ob =
+            OrderBookBuilder::new("BTCUSD").with_fees(schedule.clone()).with_accounts(true).build();
+
+        assert_eq!(ob.fees.as_ref().unwrap().schedule, schedule);
+        assert_eq!(ob.accounts.as_ref().unwrap().fee_buffer_bps, 5);
+    }
+
+    #[test]
+    fn test_builder_with_throttle() {
+        let limits = ThrottleLimits::default().with_max_messages(10, 1000);
+        let ob = OrderBookBuilder::new("BTCUSD").with_throttle(limits).build();
+
+        assert_eq!(ob.throttle.as_ref().unwrap().limits, limits);
+        assert!(ob.throttle.as_ref().unwrap().activity.is_empty());
+    }
+
+    #[test]
+    fn test_builder_with_candles() {
+        let ob = OrderBookBuilder::new("BTCUSD").build();
+        assert!(ob.candles.is_none());
+
+        let intervals = [CandleInterval::ONE_HOUR, CandleInterval::ONE_SECOND];
+        let ob = OrderBookBuilder::new("BTCUSD").with_candles(&intervals).build();
+        assert_eq!(
+            ob.candles.as_ref().unwrap().intervals,
+            vec![CandleInterval::ONE_SECOND, CandleInterval::ONE_HOUR]
+        );
+    }
+
+    #[test]
+    fn test_builder_with_conflation() {
+        let ob = OrderBookBuilder::new("BTCUSD").build();
+        assert!(ob.conflation.is_none());
+
+        let policy = ConflationPolicy::default().with_interval(100);
+        let ob = OrderBookBuilder::new("BTCUSD").with_conflation(policy).build();
+        assert_eq!(ob.conflation.as_ref().unwrap().policy, policy);
+    }
+
+    #[test]
+    fn test_builder_with_trade_history() {
+        let ob = OrderBookBuilder::new("BTCUSD").with_trade_history(10).build();
+        assert_eq!(ob.trade_history, 10);
+        assert_eq!(OrderBookBuilder::new("BTCUSD").build().trade_history, 1_000);
+    }
 }
@@ -0,0 +1,262 @@
+//! OHLCV candle aggregation.
+//!
+//! When enabled with
+//! [`OrderBookBuilder::with_candles`](crate::OrderBookBuilder::with_candles),
+//! every trade, maker and taker side alike, updates the open candle of each
+//! configured interval. Candles are aligned on the engine clock
+//! ([`OrderBook::now`](crate::OrderBook::now)): a candle is closed by the first
+//! trade, or the first [`OrderBook::set_clock`](crate::OrderBook::set_clock)
+//! call, at or after its end, and is then published to the listeners as an
+//! [`OrderBookEvent::CandleClosed`](crate::OrderBookEvent::CandleClosed) event.
+//!
+//! Intervals without any trade produce no candle.
+
+use std::ops::Add;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{make_error, ErrorType, OrderBookError, Result},
+    order::{Price, Quantity},
+    risk::notional,
+    trade::Trade,
+};
+
+/// Length of a candle, in milliseconds.
+///
+/// Any length greater than zero can be configured with
+/// [`CandleInterval::from_millis`], the usual ones are available as constants.
+///
+/// # Example
+/// ```rust
+/// use rust_order_book::CandleInterval;
+///
+/// let five_minutes = CandleInterval::from_millis(300_000).unwrap();
+/// assert_eq!(five_minutes.millis(), 5 * CandleInterval::ONE_MINUTE.millis());
+/// assert!(CandleInterval::from_millis(0).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "u64", into = "u64")]
+pub struct CandleInterval(u64);
+
+impl CandleInterval {
+    pub const ONE_SECOND: CandleInterval = CandleInterval(1_000);
+    pub const ONE_MINUTE: CandleInterval = CandleInterval(60_000);
+    pub const ONE_HOUR: CandleInterval = CandleInterval(3_600_000);
+    pub const ONE_DAY: CandleInterval = CandleInterval(86_400_000);
+
+    /// Creates an interval of `millis` milliseconds.
+    ///
+    /// # Errors
+    /// Returns `Err` if `millis` is zero.
+    pub fn from_millis(millis: u64) -> Result<Self> {
+        if millis == 0 {
+            return Err(make_error(ErrorType::InvalidCandleInterval));
+        }
+        Ok(CandleInterval(millis))
+    }
+
+    /// Length of the interval, in milliseconds.
+    pub fn millis(&self) -> i64 {
+        i64::try_from(self.0).unwrap_or(i64::MAX)
+    }
+
+    /// Start of the interval containing `ts`.
+    fn start(&self, ts: i64) -> i64 {
+        ts - ts.rem_euclid(self.millis())
+    }
+}
+
+impl TryFrom<u64> for CandleInterval {
+    type Error = OrderBookError;
+
+    fn try_from(millis: u64) -> Result<Self> {
+        Self::from_millis(millis)
+    }
+}
+
+impl From<CandleInterval> for u64 {
+    fn from(interval: CandleInterval) -> Self {
+        interval.0
+    }
+}
+
+/// Open, high, low and close prices and traded volume of an interval.
+///
+/// # Fields
+/// - `interval`: Length of the candle
+/// - `start`: Start of the candle, in milliseconds, inclusive
+/// - `open`: Price of the first trade
+/// - `high`: Highest trade price
+/// - `low`: Lowest trade price
+/// - `close`: Price of the last trade
+/// - `volume`: Total traded quantity
+/// - `notional`: Total traded notional (`price × quantity`)
+/// - `vwap`: Volume-weighted average price, rounded down
+/// - `trades`: Number of trades
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Candle {
+    pub interval: CandleInterval,
+    pub start: i64,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: Quantity,
+    pub notional: u128,
+    pub vwap: Price,
+    pub trades: u64,
+}
+
+impl Candle {
+    fn new(interval: CandleInterval, trade: &Trade) -> Self {
+        Self {
+            interval,
+            start: interval.start(trade.ts),
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: trade.quantity,
+            notional: notional(trade.price, trade.quantity),
+            vwap: trade.price,
+            trades: 1,
+        }
+    }
+
+    /// End of the candle, in milliseconds, exclusive.
+    pub fn end(&self) -> i64 {
+        self.start.saturating_add(self.interval.millis())
+    }
+
+    fn apply(&mut self, trade: &Trade) {
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.close = trade.price;
+        self.volume = self.volume.add(trade.quantity);
+        self.notional = self.notional.saturating_add(notional(trade.price, trade.quantity));
+        self.vwap = Price((self.notional / u128::from(self.volume.value().max(1))) as u64);
+        self.trades += 1;
+    }
+}
+
+/// The open candle of every configured interval.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Candles {
+    pub(crate) intervals: Vec<CandleInterval>,
+    pub(crate) open: Vec<Candle>,
+}
+
+impl Candles {
+    pub(crate) fn new(intervals: &[CandleInterval]) -> Self {
+        let mut intervals = intervals.to_vec();
+        intervals.sort();
+        intervals.dedup();
+        Self { intervals, open: Vec::new() }
+    }
+
+    /// Closes the candles ended at `now` and returns them, shortest interval first.
+    pub(crate) fn roll(&mut self, now: i64) -> Vec<Candle> {
+        let (closed, open) = self.open.iter().partition(|c| c.end() <= now);
+        self.open = open;
+        closed
+    }
+
+    /// Adds a trade to the open candles. Returns the candles it closed.
+    pub(crate) fn apply(&mut self, trade: &Trade) -> Vec<Candle> {
+        let closed = self.roll(trade.ts);
+        for interval in &self.intervals {
+            match self.open.iter_mut().find(|c| c.interval == *interval) {
+                Some(candle) => candle.apply(trade),
+                None => self.open.push(Candle::new(*interval, trade)),
+            }
+        }
+        self.open.sort_by_key(|c| c.interval);
+        closed
+    }
+
+    /// Restores the open candles of the configured intervals.
+    pub(crate) fn restore(&mut self, open: Vec<Candle>) {
+        self.open = open.into_iter().filter(|c| self.intervals.contains(&c.interval)).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{trade::TradeId, OrderId, Side};
+
+    fn trade(ts: i64, price: u64, quantity: u64) -> Trade {
+        Trade {
+            id: TradeId(0),
+            maker_order_id: OrderId(0),
+            taker_order_id: OrderId(1),
+            aggressor: Some(Side::Buy),
+            price: Price(price),
+            quantity: Quantity(quantity),
+            ts,
+        }
+    }
+
+    #[test]
+    fn test_aggregation() {
+        let mut candles = Candles::new(&[CandleInterval::ONE_MINUTE, CandleInterval::ONE_SECOND]);
+        assert!(candles.apply(&trade(1_500, 100, 1)).is_empty());
+        assert!(candles.apply(&trade(1_900, 104, 2)).is_empty());
+        assert!(candles.apply(&trade(1_999, 98, 1)).is_empty());
+
+        let closed = candles.apply(&trade(3_000, 101, 4));
+        assert_eq!(
+            closed,
+            vec![Candle {
+                interval: CandleInterval::ONE_SECOND,
+                start: 1_000,
+                open: Price(100),
+                high: Price(104),
+                low: Price(98),
+                close: Price(98),
+                volume: Quantity(4),
+                notional: 406,
+                vwap: Price(101),
+                trades: 3,
+            }]
+        );
+
+        // the clock alone closes the candles
+        assert!(candles.roll(59_999).iter().all(|c| c.interval == CandleInterval::ONE_SECOND));
+        let closed = candles.roll(60_000);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].start, 0);
+        assert_eq!(closed[0].volume, Quantity(8));
+        assert_eq!(closed[0].trades, 4);
+        assert_eq!((closed[0].open, closed[0].close), (Price(100), Price(101)));
+        assert!(candles.open.is_empty());
+    }
+
+    #[test]
+    fn test_interval_start() {
+        assert_eq!(CandleInterval::ONE_HOUR.start(7_199_999), 3_600_000);
+        assert_eq!(CandleInterval::ONE_SECOND.start(-1), -1_000);
+        let interval = CandleInterval::from_millis(300_000).unwrap();
+        assert_eq!(interval.start(899_999), 600_000);
+        assert_eq!(CandleInterval::from_millis(u64::MAX).unwrap().millis(), i64::MAX);
+    }
+
+    #[test]
+    fn test_custom_interval() {
+        assert_eq!(
+            CandleInterval::from_millis(0).unwrap_err().code,
+            make_error(ErrorType::InvalidCandleInterval).code
+        );
+        let interval = CandleInterval::from_millis(15_000).unwrap();
+        let mut candles = Candles::new(&[interval, CandleInterval::ONE_MINUTE]);
+        assert!(candles.apply(&trade(14_999, 100, 1)).is_empty());
+        let closed = candles.apply(&trade(15_000, 101, 1));
+        assert_eq!(closed.len(), 1);
+        assert_eq!((closed[0].interval, closed[0].start), (interval, 0));
+        assert_eq!(
+            candles.open.iter().map(|c| c.interval).collect::<Vec<_>>(),
+            vec![interval, CandleInterval::ONE_MINUTE]
+        );
+    }
+}
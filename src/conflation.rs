@@ -0,0 +1,176 @@
+//! Conflated level-2 feed.
+//!
+//! When enabled with
+//! [`OrderBookBuilder::with_conflation`](crate::OrderBookBuilder::with_conflation),
+//! the level-2 updates are merged per price level and published in batches as
+//! [`OrderBookEvent::ConflatedDepth`](crate::OrderBookEvent::ConflatedDepth)
+//! events, so that a slow consumer receives the latest quantity of every
+//! changed level instead of every intermediate update.
+//!
+//! A batch is flushed at the end of the operation that reaches the configured
+//! number of updates, or once the configured interval has elapsed on the engine
+//! clock ([`OrderBook::now`](crate::OrderBook::now)), checked at the end of
+//! every operation and on every
+//! [`OrderBook::set_clock`](crate::OrderBook::set_clock) call. Batches never
+//! split an operation, so a consumer never sees a crossed intermediate state.
+//!
+//! Every batch carries its own sequence number, incremented by one on every
+//! batch, and the sequence number of the last level-2 update it includes. A
+//! consumer bootstraps from
+//! [`OrderBook::depth_snapshot`](crate::OrderBook::depth_snapshot), then applies
+//! the batches: since levels carry absolute quantities, applying a batch that
+//! partially overlaps the snapshot is harmless.
+
+use std::collections::BTreeMap;
+
+use crate::{market_data::DepthUpdate, order::Price, Side};
+
+/// When the conflated level-2 updates are flushed.
+///
+/// Every trigger is optional: a `None` value disables it. With no trigger,
+/// batches are only flushed by [`OrderBook::flush_conflated`](crate::OrderBook::flush_conflated).
+///
+/// # Fields
+/// - `interval_ms`: Minimum time between two batches, in milliseconds
+/// - `max_updates`: Number of level-2 updates that triggers a batch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConflationPolicy {
+    pub interval_ms: Option<i64>,
+    pub max_updates: Option<usize>,
+}
+
+impl ConflationPolicy {
+    /// Flushes the updates once `interval_ms` milliseconds have elapsed since
+    /// the last batch.
+    pub fn with_interval(mut self, interval_ms: i64) -> Self {
+        self.interval_ms = Some(interval_ms);
+        self
+    }
+
+    /// Flushes the updates once `max_updates` level-2 updates are pending.
+    pub fn with_max_updates(mut self, max_updates: usize) -> Self {
+        self.max_updates = Some(max_updates);
+        self
+    }
+}
+
+/// A batch of conflated level-2 updates.
+///
+/// # Fields
+/// - `sequence`: Sequence number of the batch
+/// - `depth_sequence`: Sequence number of the last level-2 update included
+/// - `updates`: The last update of every changed level, in sequence order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflatedDepthUpdate {
+    pub sequence: u64,
+    pub depth_sequence: u64,
+    pub updates: Vec<DepthUpdate>,
+}
+
+/// The pending level-2 updates, merged per price level.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Conflator {
+    pub(crate) policy: ConflationPolicy,
+    pub(crate) sequence: u64,
+    bids: BTreeMap<Price, DepthUpdate>,
+    asks: BTreeMap<Price, DepthUpdate>,
+    pending_updates: usize,
+    last_flush: Option<i64>,
+}
+
+impl Conflator {
+    pub(crate) fn new(policy: ConflationPolicy) -> Self {
+        Self {
+            policy,
+            sequence: 0,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            pending_updates: 0,
+            last_flush: None,
+        }
+    }
+
+    /// Merges a level-2 update into the pending batch. `now` starts the first
+    /// interval.
+    pub(crate) fn record(&mut self, update: DepthUpdate, now: i64) {
+        self.last_flush.get_or_insert(now);
+        let levels = match update.side {
+            Side::Buy => &mut self.bids,
+            Side::Sell => &mut self.asks,
+        };
+        levels.insert(update.price, update);
+        self.pending_updates += 1;
+    }
+
+    /// Returns `true` if the pending batch must be flushed at `now`.
+    pub(crate) fn is_due(&self, now: i64) -> bool {
+        if self.pending_updates == 0 {
+            return false;
+        }
+        self.policy.max_updates.is_some_and(|max| self.pending_updates >= max)
+            || self
+                .policy
+                .interval_ms
+                .zip(self.last_flush)
+                .is_some_and(|(interval, last)| now.saturating_sub(last) >= interval)
+    }
+
+    /// Takes the pending batch, if any.
+    pub(crate) fn flush(&mut self, now: i64) -> Option<ConflatedDepthUpdate> {
+        if self.pending_updates == 0 {
+            return None;
+        }
+        let bids = std::mem::take(&mut self.bids);
+        let asks = std::mem::take(&mut self.asks);
+        let mut updates: Vec<DepthUpdate> = bids.into_values().chain(asks.into_values()).collect();
+        updates.sort_by_key(|u| u.sequence);
+        self.pending_updates = 0;
+        self.last_flush = Some(now);
+        self.sequence += 1;
+        Some(ConflatedDepthUpdate {
+            sequence: self.sequence,
+            depth_sequence: updates.last().map_or(0, |u| u.sequence),
+            updates,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::Quantity;
+
+    fn update(sequence: u64, side: Side, price: u64, quantity: u64) -> DepthUpdate {
+        DepthUpdate { sequence, side, price: Price(price), quantity: Quantity(quantity) }
+    }
+
+    #[test]
+    fn test_conflation() {
+        let policy = ConflationPolicy::default().with_interval(100).with_max_updates(3);
+        let mut conflator = Conflator::new(policy);
+        assert!(!conflator.is_due(1_000));
+        assert_eq!(conflator.flush(1_000), None);
+
+        conflator.record(update(1, Side::Buy, 100, 5), 1_000);
+        conflator.record(update(2, Side::Buy, 100, 3), 1_010);
+        assert!(!conflator.is_due(1_099));
+        assert!(conflator.is_due(1_100));
+        conflator.record(update(3, Side::Sell, 101, 2), 1_020);
+        // the update count triggers before the interval
+        assert!(conflator.is_due(1_020));
+
+        let batch = conflator.flush(1_020).unwrap();
+        assert_eq!(batch.sequence, 1);
+        assert_eq!(batch.depth_sequence, 3);
+        assert_eq!(
+            batch.updates,
+            vec![update(2, Side::Buy, 100, 3), update(3, Side::Sell, 101, 2)]
+        );
+
+        // the next interval starts at the last flush
+        conflator.record(update(4, Side::Sell, 101, 0), 1_050);
+        assert!(!conflator.is_due(1_119));
+        assert!(conflator.is_due(1_120));
+        assert_eq!(conflator.flush(1_120).unwrap().sequence, 2);
+    }
+}
@@ -6,7 +6,8 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    order::{OrderId, Price, Quantity},
+    accounts::Asset,
+    order::{OrderId, OwnerId, Price, Quantity},
     LimitOrderOptions, MarketOrderOptions,
 };
 
@@ -18,11 +19,38 @@ pub enum OrderType {
     Market,
     /// A limit order that rests on the book until matched or canceled.
     Limit,
+    /// A market order executed only in the opening auction (MOO).
+    MarketOnOpen,
+    /// A limit order executed only in the opening auction (LOO).
+    LimitOnOpen,
+    /// A market order executed only in the closing auction (MOC).
+    MarketOnClose,
+    /// A limit order executed only in the closing auction (LOC).
+    LimitOnClose,
     // StopMarket,
     // StopLimit,
     // OCO
 }
 
+impl OrderType {
+    /// Returns the auction phase an auction-only order takes part in:
+    /// `PreOpen` for the opening auction and `ClosingAuction` for the closing one.
+    pub fn auction_phase(self) -> Option<TradingPhase> {
+        match self {
+            OrderType::MarketOnOpen | OrderType::LimitOnOpen => Some(TradingPhase::PreOpen),
+            OrderType::MarketOnClose | OrderType::LimitOnClose => {
+                Some(TradingPhase::ClosingAuction)
+            }
+            OrderType::Market | OrderType::Limit => None,
+        }
+    }
+
+    /// Returns `true` for the order types without a limit price.
+    pub(crate) fn is_market(self) -> bool {
+        matches!(self, OrderType::Market | OrderType::MarketOnOpen | OrderType::MarketOnClose)
+    }
+}
+
 /// Represents the side of an order: buy or sell.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -61,6 +89,69 @@ pub enum OrderStatus {
     Rejected,
 }
 
+/// The trading phase of the order book.
+///
+/// Each phase restricts the operations the book accepts ("On open/close" are the
+/// auction-only orders, see [`OrderType::auction_phase`]):
+///
+/// | Phase            | Market | Limit                 | Modify | Cancel | On open/close |
+/// |------------------|--------|-----------------------|--------|--------|---------------|
+/// | `PreOpen`        | no     | GTC only, no matching | yes    | yes    | yes           |
+/// | `Continuous`     | yes    | yes                   | yes    | yes    | yes           |
+/// | `Auction`        | no     | GTC only, no matching | yes    | yes    | yes           |
+/// | `ClosingAuction` | no     | GTC only, no matching | yes    | yes    | yes           |
+/// | `Halted`         | no     | no                    | no     | yes    | no            |
+/// | `Closed`         | no     | no                    | no     | no     | no            |
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingPhase {
+    /// Orders are collected without matching, before the market opens.
+    PreOpen,
+    /// Orders are matched as soon as they are received.
+    #[default]
+    Continuous,
+    /// An intraday or closing call auction: orders are collected without
+    /// matching until the book is uncrossed.
+    Auction,
+    /// The closing call auction, in which market-on-close and limit-on-close
+    /// orders take part.
+    ClosingAuction,
+    /// Trading is suspended: resting orders can only be canceled.
+    Halted,
+    /// The market is closed: no operation is accepted.
+    Closed,
+}
+
+impl TradingPhase {
+    /// Returns `true` if the book can move from this phase to `next`.
+    ///
+    /// Allowed transitions are:
+    /// - `PreOpen` → `Continuous`, `Halted`, `Closed`
+    /// - `Continuous` → `Auction`, `ClosingAuction`, `Halted`, `Closed`
+    /// - `Auction` → `Continuous`, `Halted`, `Closed`
+    /// - `ClosingAuction` → `Halted`, `Closed`
+    /// - `Halted` → `PreOpen`, `Continuous`, `Auction`, `ClosingAuction`, `Closed`
+    /// - `Closed` → `PreOpen`
+    pub fn can_transition_to(self, next: TradingPhase) -> bool {
+        use TradingPhase::*;
+        matches!(
+            (self, next),
+            (PreOpen, Continuous | Halted | Closed)
+                | (Continuous, Auction | ClosingAuction | Halted | Closed)
+                | (Auction, Continuous | Halted | Closed)
+                | (ClosingAuction, Halted | Closed)
+                | (Halted, PreOpen | Continuous | Auction | ClosingAuction | Closed)
+                | (Closed, PreOpen)
+        )
+    }
+
+    /// Returns `true` for the phases in which orders are collected without
+    /// matching: `PreOpen`, `Auction` and `ClosingAuction`.
+    pub fn is_auction(self) -> bool {
+        matches!(self, TradingPhase::PreOpen | TradingPhase::Auction | TradingPhase::ClosingAuction)
+    }
+}
+
 /// Represents the type of operation recorded in the order book journal.
 ///
 /// This enum provides a type-safe and explicit way to indicate which kind of
@@ -76,14 +167,49 @@ pub enum JournalOp {
     Modify,
     /// Cancel (delete) order
     Cancel,
+    /// Cancel of an order by the close of its session, in any trading phase
+    SessionCancel,
+    /// Set the external reference price used by the price band
+    SetReferencePrice,
+    /// Deposit funds into an account
+    Deposit,
+    /// Withdraw funds from an account
+    Withdraw,
+    /// Change the trading phase
+    SetPhase,
+    /// Uncross the book at the auction price
+    Uncross,
+    /// Market-on-open order
+    MarketOnOpen,
+    /// Limit-on-open order
+    LimitOnOpen,
+    /// Market-on-close order
+    MarketOnClose,
+    /// Limit-on-close order
+    LimitOnClose,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Input of a journaled operation.
+///
+/// Serialized with an adjacent tag: the snake case name of the variant in
+/// `type` and its payload, if any, in `data`, e.g.
+/// `{"type":"cancel","data":3}` or `{"type":"uncross"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum OrderOptions {
     Market(MarketOrderOptions),
     Limit(LimitOrderOptions),
     Modify { id: OrderId, price: Option<Price>, quantity: Option<Quantity> },
     Cancel(OrderId),
+    SetReferencePrice(Price),
+    Deposit { owner: OwnerId, asset: Asset, amount: u128 },
+    Withdraw { owner: OwnerId, asset: Asset, amount: u128 },
+    SetPhase(TradingPhase),
+    Uncross,
+    MarketOnOpen(MarketOrderOptions),
+    LimitOnOpen(LimitOrderOptions),
+    MarketOnClose(MarketOrderOptions),
+    LimitOnClose(LimitOrderOptions),
 }
 
 #[cfg(test)]
@@ -98,6 +224,8 @@ mod tests {
 
         assert_eq!(to_string(&OrderType::Market).unwrap(), "\"market\"");
         assert_eq!(to_string(&OrderType::Limit).unwrap(), "\"limit\"");
+        assert_eq!(to_string(&OrderType::MarketOnOpen).unwrap(), "\"market_on_open\"");
+        assert_eq!(to_string(&OrderType::LimitOnClose).unwrap(), "\"limit_on_close\"");
 
         assert_eq!(to_string(&OrderStatus::New).unwrap(), "\"new\"");
         assert_eq!(to_string(&OrderStatus::PartiallyFilled).unwrap(), "\"partially_filled\"");
@@ -112,5 +240,51 @@ mod tests {
         assert_eq!(to_string(&JournalOp::Market).unwrap(), "\"market\"");
         assert_eq!(to_string(&JournalOp::Limit).unwrap(), "\"limit\"");
         assert_eq!(to_string(&JournalOp::Cancel).unwrap(), "\"cancel\"");
+        assert_eq!(to_string(&JournalOp::SessionCancel).unwrap(), "\"session_cancel\"");
+        assert_eq!(to_string(&JournalOp::SetReferencePrice).unwrap(), "\"set_reference_price\"");
+        assert_eq!(to_string(&JournalOp::Deposit).unwrap(), "\"deposit\"");
+        assert_eq!(to_string(&JournalOp::Withdraw).unwrap(), "\"withdraw\"");
+        assert_eq!(to_string(&JournalOp::SetPhase).unwrap(), "\"set_phase\"");
+        assert_eq!(to_string(&JournalOp::Uncross).unwrap(), "\"uncross\"");
+        assert_eq!(to_string(&JournalOp::MarketOnOpen).unwrap(), "\"market_on_open\"");
+        assert_eq!(to_string(&JournalOp::LimitOnOpen).unwrap(), "\"limit_on_open\"");
+        assert_eq!(to_string(&JournalOp::MarketOnClose).unwrap(), "\"market_on_close\"");
+        assert_eq!(to_string(&JournalOp::LimitOnClose).unwrap(), "\"limit_on_close\"");
+
+        assert_eq!(to_string(&TradingPhase::PreOpen).unwrap(), "\"pre_open\"");
+        assert_eq!(to_string(&TradingPhase::Continuous).unwrap(), "\"continuous\"");
+        assert_eq!(to_string(&TradingPhase::Auction).unwrap(), "\"auction\"");
+        assert_eq!(to_string(&TradingPhase::ClosingAuction).unwrap(), "\"closing_auction\"");
+        assert_eq!(to_string(&TradingPhase::Halted).unwrap(), "\"halted\"");
+        assert_eq!(to_string(&TradingPhase::Closed).unwrap(), "\"closed\"");
+    }
+
+    #[test]
+    fn test_trading_phase_transitions() {
+        use TradingPhase::*;
+        assert_eq!(TradingPhase::default(), Continuous);
+        assert!(PreOpen.can_transition_to(Continuous));
+        assert!(Continuous.can_transition_to(Halted));
+        assert!(Halted.can_transition_to(Continuous));
+        assert!(Closed.can_transition_to(PreOpen));
+        assert!(!Continuous.can_transition_to(Continuous));
+        assert!(!Continuous.can_transition_to(PreOpen));
+        assert!(!Closed.can_transition_to(Continuous));
+        assert!(Continuous.can_transition_to(Auction));
+        assert!(!PreOpen.can_transition_to(Auction));
+        assert!(PreOpen.is_auction());
+        assert!(Auction.is_auction());
+        assert!(!Continuous.is_auction());
+        assert!(ClosingAuction.is_auction());
+        assert!(!ClosingAuction.can_transition_to(Continuous));
+    }
+
+    #[test]
+    fn test_order_type_auction_phase() {
+        assert_eq!(OrderType::MarketOnOpen.auction_phase(), Some(TradingPhase::PreOpen));
+        assert_eq!(OrderType::LimitOnOpen.auction_phase(), Some(TradingPhase::PreOpen));
+        assert_eq!(OrderType::MarketOnClose.auction_phase(), Some(TradingPhase::ClosingAuction));
+        assert_eq!(OrderType::LimitOnClose.auction_phase(), Some(TradingPhase::ClosingAuction));
+        assert_eq!(OrderType::Limit.auction_phase(), None);
     }
 }
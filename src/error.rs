@@ -14,14 +14,43 @@ pub(crate) enum ErrorType {
     InvalidQuantity,
     OrderAlredyExists,
     OrderNotFound,
+    PriceNotMultipleOfTick,
+    QuantityNotMultipleOfLot,
+    QuantityBelowMinimum,
+    QuantityAboveMaximum,
+    NotionalBelowMinimum,
+    InvalidDepthGrouping,
+    InvalidCandleInterval,
     OrderPostOnly,
     OrderIOC,
     OrderFOK,
+    OrderOutsidePriceBand,
 
     // 12xx Internal error
     InsufficientQuantity,
     InvalidPriceLevel,
     OrderBookEmpty,
+
+    // 13xx Risk issues
+    RiskMaxOrderQty,
+    RiskMaxNotional,
+    RiskMaxOpenOrders,
+    ThrottleMessageRate,
+    ThrottleOrderToTradeRatio,
+
+    // 14xx Account issues
+    InsufficientBalance,
+    OwnerRequired,
+    AccountsDisabled,
+    AuctionMarketBuyUnfunded,
+
+    // 15xx Trading phase issues
+    MarketClosed,
+    TradingHalted,
+    OrderNotAllowedInPhase,
+    InvalidPhaseTransition,
+    NotInAuction,
+    AuctionOrderNotModifiable,
 }
 
 impl ErrorType {
@@ -38,13 +67,42 @@ impl ErrorType {
             ErrorType::OrderPostOnly => 1104,
             ErrorType::OrderIOC => 1105,
             ErrorType::OrderFOK => 1106,
+            ErrorType::OrderOutsidePriceBand => 1107,
             ErrorType::OrderAlredyExists => 1109,
             ErrorType::OrderNotFound => 1110,
+            ErrorType::PriceNotMultipleOfTick => 1111,
+            ErrorType::QuantityNotMultipleOfLot => 1112,
+            ErrorType::QuantityBelowMinimum => 1113,
+            ErrorType::QuantityAboveMaximum => 1114,
+            ErrorType::NotionalBelowMinimum => 1115,
+            ErrorType::InvalidDepthGrouping => 1116,
+            ErrorType::InvalidCandleInterval => 1117,
 
             // 12xx Internal error
             ErrorType::OrderBookEmpty => 1200,
             ErrorType::InsufficientQuantity => 1201,
             ErrorType::InvalidPriceLevel => 1202,
+
+            // 13xx Risk issues
+            ErrorType::RiskMaxOrderQty => 1300,
+            ErrorType::RiskMaxNotional => 1301,
+            ErrorType::RiskMaxOpenOrders => 1302,
+            ErrorType::ThrottleMessageRate => 1303,
+            ErrorType::ThrottleOrderToTradeRatio => 1304,
+
+            // 14xx Account issues
+            ErrorType::InsufficientBalance => 1400,
+            ErrorType::OwnerRequired => 1401,
+            ErrorType::AccountsDisabled => 1402,
+            ErrorType::AuctionMarketBuyUnfunded => 1403,
+
+            // 15xx Trading phase issues
+            ErrorType::MarketClosed => 1500,
+            ErrorType::TradingHalted => 1501,
+            ErrorType::OrderNotAllowedInPhase => 1502,
+            ErrorType::InvalidPhaseTransition => 1503,
+            ErrorType::NotInAuction => 1504,
+            ErrorType::AuctionOrderNotModifiable => 1505,
         }
     }
 
@@ -65,13 +123,46 @@ impl ErrorType {
                 "IOC order rejected: no immediate liquidity available at requested price"
             }
             ErrorType::OrderFOK => "FOK order rejected: unable to fill entire quantity immediately",
+            ErrorType::OrderOutsidePriceBand => "Order rejected: price is outside the price band",
             ErrorType::OrderAlredyExists => "Order already exists",
             ErrorType::OrderNotFound => "Order not found",
+            ErrorType::PriceNotMultipleOfTick => "Price must be a multiple of the tick size",
+            ErrorType::QuantityNotMultipleOfLot => "Quantity must be a multiple of the lot size",
+            ErrorType::QuantityBelowMinimum => "Quantity is below the minimum order quantity",
+            ErrorType::QuantityAboveMaximum => "Quantity is above the maximum order quantity",
+            ErrorType::NotionalBelowMinimum => "Order notional is below the minimum notional",
+            ErrorType::InvalidDepthGrouping => "Depth grouping step must be greater than zero",
+            ErrorType::InvalidCandleInterval => "Candle interval must be greater than zero",
 
             // 12xx Internal error
             ErrorType::OrderBookEmpty => "Order book is empty",
             ErrorType::InsufficientQuantity => "Insufficient quantity to calculate price",
             ErrorType::InvalidPriceLevel => "Invalid order price level",
+
+            // 13xx Risk issues
+            ErrorType::RiskMaxOrderQty => "Risk check failed: order quantity exceeds the maximum",
+            ErrorType::RiskMaxNotional => "Risk check failed: order notional exceeds the maximum",
+            ErrorType::RiskMaxOpenOrders => "Risk check failed: owner has too many resting orders",
+            ErrorType::ThrottleMessageRate => "Throttled: owner exceeded the message rate limit",
+            ErrorType::ThrottleOrderToTradeRatio => {
+                "Throttled: owner exceeded the order-to-trade ratio"
+            }
+
+            // 14xx Account issues
+            ErrorType::InsufficientBalance => "Insufficient free balance",
+            ErrorType::OwnerRequired => "Order owner is required when accounts are enabled",
+            ErrorType::AccountsDisabled => "Accounts are not enabled on this order book",
+            ErrorType::AuctionMarketBuyUnfunded => {
+                "Market-on-open/close buy orders cannot be funded when accounts are enabled"
+            }
+
+            // 15xx Trading phase issues
+            ErrorType::MarketClosed => "Order book is closed",
+            ErrorType::TradingHalted => "Trading is halted: only cancels are accepted",
+            ErrorType::OrderNotAllowedInPhase => "Order not accepted in the current trading phase",
+            ErrorType::InvalidPhaseTransition => "Invalid trading phase transition",
+            ErrorType::NotInAuction => "Uncross is only allowed during an auction phase",
+            ErrorType::AuctionOrderNotModifiable => "Auction-only orders cannot be modified",
         }
     }
 }
@@ -128,13 +219,42 @@ pub(crate) fn default_message_for_code(code: u32) -> Cow<'static, str> {
         1104 => Cow::Borrowed(ErrorType::OrderPostOnly.message()),
         1105 => Cow::Borrowed(ErrorType::OrderIOC.message()),
         1106 => Cow::Borrowed(ErrorType::OrderFOK.message()),
+        1107 => Cow::Borrowed(ErrorType::OrderOutsidePriceBand.message()),
         1109 => Cow::Borrowed(ErrorType::OrderAlredyExists.message()),
         1110 => Cow::Borrowed(ErrorType::OrderNotFound.message()),
+        1111 => Cow::Borrowed(ErrorType::PriceNotMultipleOfTick.message()),
+        1112 => Cow::Borrowed(ErrorType::QuantityNotMultipleOfLot.message()),
+        1113 => Cow::Borrowed(ErrorType::QuantityBelowMinimum.message()),
+        1114 => Cow::Borrowed(ErrorType::QuantityAboveMaximum.message()),
+        1115 => Cow::Borrowed(ErrorType::NotionalBelowMinimum.message()),
+        1116 => Cow::Borrowed(ErrorType::InvalidDepthGrouping.message()),
+        1117 => Cow::Borrowed(ErrorType::InvalidCandleInterval.message()),
 
         // 12xx Internal error
         1200 => Cow::Borrowed(ErrorType::InsufficientQuantity.message()),
         1201 => Cow::Borrowed(ErrorType::InvalidPriceLevel.message()),
 
+        // 13xx Risk issues
+        1300 => Cow::Borrowed(ErrorType::RiskMaxOrderQty.message()),
+        1301 => Cow::Borrowed(ErrorType::RiskMaxNotional.message()),
+        1302 => Cow::Borrowed(ErrorType::RiskMaxOpenOrders.message()),
+        1303 => Cow::Borrowed(ErrorType::ThrottleMessageRate.message()),
+        1304 => Cow::Borrowed(ErrorType::ThrottleOrderToTradeRatio.message()),
+
+        // 14xx Account issues
+        1400 => Cow::Borrowed(ErrorType::InsufficientBalance.message()),
+        1401 => Cow::Borrowed(ErrorType::OwnerRequired.message()),
+        1402 => Cow::Borrowed(ErrorType::AccountsDisabled.message()),
+        1403 => Cow::Borrowed(ErrorType::AuctionMarketBuyUnfunded.message()),
+
+        // 15xx Trading phase issues
+        1500 => Cow::Borrowed(ErrorType::MarketClosed.message()),
+        1501 => Cow::Borrowed(ErrorType::TradingHalted.message()),
+        1502 => Cow::Borrowed(ErrorType::OrderNotAllowedInPhase.message()),
+        1503 => Cow::Borrowed(ErrorType::InvalidPhaseTransition.message()),
+        1504 => Cow::Borrowed(ErrorType::NotInAuction.message()),
+        1505 => Cow::Borrowed(ErrorType::AuctionOrderNotModifiable.message()),
+
         _ => Cow::Owned(format!("Unknown error ({code})")),
     }
 }
@@ -215,11 +335,74 @@ mod tests {
                 1106,
                 "FOK order rejected: unable to fill entire quantity immediately",
             ),
+            (
+                ErrorType::OrderOutsidePriceBand,
+                1107,
+                "Order rejected: price is outside the price band",
+            ),
             (ErrorType::OrderAlredyExists, 1109, "Order already exists"),
             (ErrorType::OrderNotFound, 1110, "Order not found"),
+            (ErrorType::PriceNotMultipleOfTick, 1111, "Price must be a multiple of the tick size"),
+            (
+                ErrorType::QuantityNotMultipleOfLot,
+                1112,
+                "Quantity must be a multiple of the lot size",
+            ),
+            (ErrorType::QuantityBelowMinimum, 1113, "Quantity is below the minimum order quantity"),
+            (ErrorType::QuantityAboveMaximum, 1114, "Quantity is above the maximum order quantity"),
+            (ErrorType::NotionalBelowMinimum, 1115, "Order notional is below the minimum notional"),
+            (
+                ErrorType::InvalidDepthGrouping,
+                1116,
+                "Depth grouping step must be greater than zero",
+            ),
+            (ErrorType::InvalidCandleInterval, 1117, "Candle interval must be greater than zero"),
             (ErrorType::OrderBookEmpty, 1200, "Order book is empty"),
             (ErrorType::InsufficientQuantity, 1201, "Insufficient quantity to calculate price"),
             (ErrorType::InvalidPriceLevel, 1202, "Invalid order price level"),
+            (
+                ErrorType::RiskMaxOrderQty,
+                1300,
+                "Risk check failed: order quantity exceeds the maximum",
+            ),
+            (
+                ErrorType::RiskMaxNotional,
+                1301,
+                "Risk check failed: order notional exceeds the maximum",
+            ),
+            (
+                ErrorType::RiskMaxOpenOrders,
+                1302,
+                "Risk check failed: owner has too many resting orders",
+            ),
+            (
+                ErrorType::ThrottleMessageRate,
+                1303,
+                "Throttled: owner exceeded the message rate limit",
+            ),
+            (
+                ErrorType::ThrottleOrderToTradeRatio,
+                1304,
+                "Throttled: owner exceeded the order-to-trade ratio",
+            ),
+            (ErrorType::InsufficientBalance, 1400, "Insufficient free balance"),
+            (ErrorType::OwnerRequired, 1401, "Order owner is required when accounts are enabled"),
+            (ErrorType::AccountsDisabled, 1402, "Accounts are not enabled on this order book"),
+            (
+                ErrorType::AuctionMarketBuyUnfunded,
+                1403,
+                "Market-on-open/close buy orders cannot be funded when accounts are enabled",
+            ),
+            (ErrorType::MarketClosed, 1500, "Order book is closed"),
+            (ErrorType::TradingHalted, 1501, "Trading is halted: only cancels are accepted"),
+            (
+                ErrorType::OrderNotAllowedInPhase,
+                1502,
+                "Order not accepted in the current trading phase",
+            ),
+            (ErrorType::InvalidPhaseTransition, 1503, "Invalid trading phase transition"),
+            (ErrorType::NotInAuction, 1504, "Uncross is only allowed during an auction phase"),
+            (ErrorType::AuctionOrderNotModifiable, 1505, "Auction-only orders cannot be modified"),
         ];
 
         for (err_type, code, msg) in cases {
@@ -253,10 +436,33 @@ mod tests {
         assert_eq!(default_message_for_code(1104), ErrorType::OrderPostOnly.message());
         assert_eq!(default_message_for_code(1105), ErrorType::OrderIOC.message());
         assert_eq!(default_message_for_code(1106), ErrorType::OrderFOK.message());
+        assert_eq!(default_message_for_code(1107), ErrorType::OrderOutsidePriceBand.message());
         assert_eq!(default_message_for_code(1109), ErrorType::OrderAlredyExists.message());
         assert_eq!(default_message_for_code(1110), ErrorType::OrderNotFound.message());
+        assert_eq!(default_message_for_code(1111), ErrorType::PriceNotMultipleOfTick.message());
+        assert_eq!(default_message_for_code(1112), ErrorType::QuantityNotMultipleOfLot.message());
+        assert_eq!(default_message_for_code(1113), ErrorType::QuantityBelowMinimum.message());
+        assert_eq!(default_message_for_code(1114), ErrorType::QuantityAboveMaximum.message());
+        assert_eq!(default_message_for_code(1115), ErrorType::NotionalBelowMinimum.message());
+        assert_eq!(default_message_for_code(1116), ErrorType::InvalidDepthGrouping.message());
+        assert_eq!(default_message_for_code(1117), ErrorType::InvalidCandleInterval.message());
         assert_eq!(default_message_for_code(1200), ErrorType::InsufficientQuantity.message());
         assert_eq!(default_message_for_code(1201), ErrorType::InvalidPriceLevel.message());
+        assert_eq!(default_message_for_code(1300), ErrorType::RiskMaxOrderQty.message());
+        assert_eq!(default_message_for_code(1301), ErrorType::RiskMaxNotional.message());
+        assert_eq!(default_message_for_code(1302), ErrorType::RiskMaxOpenOrders.message());
+        assert_eq!(default_message_for_code(1303), ErrorType::ThrottleMessageRate.message());
+        assert_eq!(default_message_for_code(1304), ErrorType::ThrottleOrderToTradeRatio.message());
+        assert_eq!(default_message_for_code(1400), ErrorType::InsufficientBalance.message());
+        assert_eq!(default_message_for_code(1401), ErrorType::OwnerRequired.message());
+        assert_eq!(default_message_for_code(1402), ErrorType::AccountsDisabled.message());
+        assert_eq!(default_message_for_code(1403), ErrorType::AuctionMarketBuyUnfunded.message());
+        assert_eq!(default_message_for_code(1500), ErrorType::MarketClosed.message());
+        assert_eq!(default_message_for_code(1501), ErrorType::TradingHalted.message());
+        assert_eq!(default_message_for_code(1502), ErrorType::OrderNotAllowedInPhase.message());
+        assert_eq!(default_message_for_code(1503), ErrorType::InvalidPhaseTransition.message());
+        assert_eq!(default_message_for_code(1504), ErrorType::NotInAuction.message());
+        assert_eq!(default_message_for_code(1505), ErrorType::AuctionOrderNotModifiable.message());
     }
 
     #[test]
@@ -0,0 +1,114 @@
+//! Typed events emitted by the order book on every mutation.
+//!
+//! Listeners are registered with
+//! [`OrderBookBuilder::with_listener`](crate::OrderBookBuilder::with_listener)
+//! and are called synchronously, in registration order, while the operation is
+//! being executed. Every order involved in a fill is notified, resting makers
+//! included.
+//!
+//! A modify is executed as a cancel followed by a new order: the old order is
+//! reported as canceled, the new one as accepted, then an
+//! [`OrderBookEvent::OrderModified`] links both.
+
+use crate::{
+    candles::Candle,
+    conflation::ConflatedDepthUpdate,
+    market_data::{BboUpdate, DepthUpdate, L3Update},
+    order::{OrderId, OwnerId, Price, Quantity},
+    trade::Trade,
+    OrderBookError, OrderType, Side,
+};
+
+/// An event emitted by the order book.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderBookEvent {
+    /// A new order passed every check and was assigned an id. `price` is `None`
+    /// for market orders.
+    OrderAccepted {
+        id: OrderId,
+        order_type: OrderType,
+        side: Side,
+        price: Option<Price>,
+        quantity: Quantity,
+        owner: Option<OwnerId>,
+    },
+    /// A new order, or the replacement of a modified order, was rejected.
+    OrderRejected {
+        order_type: OrderType,
+        side: Side,
+        owner: Option<OwnerId>,
+        error: OrderBookError,
+    },
+    /// An order, or its unfilled part, was added to the book.
+    OrderRested { id: OrderId, side: Side, price: Price, quantity: Quantity },
+    /// An order was executed for `quantity` at `price` and `remaining_qty` is
+    /// still open.
+    OrderPartiallyFilled {
+        id: OrderId,
+        side: Side,
+        price: Price,
+        quantity: Quantity,
+        remaining_qty: Quantity,
+    },
+    /// The last `quantity` of an order was executed at `price`.
+    OrderFilled { id: OrderId, side: Side, price: Price, quantity: Quantity },
+    /// An order was canceled with `remaining_qty` unfilled, either on request or
+    /// because its remainder could not rest in the book.
+    OrderCanceled { id: OrderId, side: Side, remaining_qty: Quantity },
+    /// The order `id` was replaced by the order `new_id`.
+    OrderModified { id: OrderId, new_id: OrderId, price: Price, quantity: Quantity },
+    /// The first order was added at a price level.
+    PriceLevelAdded { side: Side, price: Price },
+    /// The last order was removed from a price level.
+    PriceLevelRemoved { side: Side, price: Price },
+    /// The total quantity of a price level changed, see [`crate::DepthUpdate`].
+    DepthUpdate(DepthUpdate),
+    /// A message of the market-by-order feed, see [`crate::L3Message`].
+    L3Update(L3Update),
+    /// The best bid or the best ask changed, see [`crate::BboUpdate`].
+    BboUpdate(BboUpdate),
+    /// A trade was executed, see [`crate::Trade`].
+    Trade(Trade),
+    /// A candle was closed, see [`crate::Candle`].
+    CandleClosed(Candle),
+    /// A batch of the conflated level-2 feed, see [`crate::ConflatedDepthUpdate`].
+    ConflatedDepth(ConflatedDepthUpdate),
+}
+
+impl OrderBookEvent {
+    /// Returns the fill event of an order executed for `quantity` at `price`.
+    pub(crate) fn fill(
+        id: OrderId,
+        side: Side,
+        price: Price,
+        quantity: Quantity,
+        remaining_qty: Quantity,
+    ) -> Self {
+        if remaining_qty.value() == 0 {
+            OrderBookEvent::OrderFilled { id, side, price, quantity }
+        } else {
+            OrderBookEvent::OrderPartiallyFilled { id, side, price, quantity, remaining_qty }
+        }
+    }
+}
+
+/// Receives the events of an order book.
+///
+/// # Example
+/// ```rust
+/// use rust_order_book::{OrderBookBuilder, OrderBookEvent, OrderBookListener};
+///
+/// struct Logger;
+///
+/// impl OrderBookListener for Logger {
+///     fn on_event(&mut self, event: &OrderBookEvent) {
+///         println!("{event:?}");
+///     }
+/// }
+///
+/// let ob = OrderBookBuilder::new("BTCUSD").with_listener(Logger).build();
+/// ```
+pub trait OrderBookListener: Send {
+    /// Called for every event, while the operation that caused it is executed.
+    fn on_event(&mut self, event: &OrderBookEvent);
+}
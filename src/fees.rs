@@ -0,0 +1,193 @@
+//! Maker/taker fee schedules.
+//!
+//! When a [`FeeSchedule`] is configured with
+//! [`OrderBookBuilder::with_fees`](crate::OrderBookBuilder::with_fees), every fill
+//! is charged a maker fee (for the resting order) and a taker fee (for the
+//! incoming order). Rates are expressed in basis points of the fill notional
+//! (`price × quantity`) and a negative maker rate is a rebate.
+//!
+//! Fees are integers in quote units and are always rounded up (towards positive
+//! infinity), so a fee is never undercharged and a rebate is never overpaid.
+
+use std::collections::HashMap;
+
+use crate::order::OwnerId;
+
+/// Maker and taker rates applied from a given traded volume.
+///
+/// # Fields
+/// - `min_volume`: Cumulative traded notional an owner needs to reach this tier
+/// - `maker_bps`: Maker rate in basis points (negative for rebates)
+/// - `taker_bps`: Taker rate in basis points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeTier {
+    pub min_volume: u128,
+    pub maker_bps: i64,
+    pub taker_bps: i64,
+}
+
+/// A volume-tiered maker/taker fee schedule.
+///
+/// The tier of an owner is chosen by its cumulative traded notional, both as
+/// maker and as taker. Orders without an owner always pay the base tier.
+///
+/// # Example
+/// ```rust
+/// use rust_order_book::FeeSchedule;
+///
+/// // 2 bps maker / 5 bps taker, then a 1 bps rebate / 3 bps taker above 1M
+/// let fees = FeeSchedule::new(2, 5).with_tier(1_000_000, -1, 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeSchedule {
+    tiers: Vec<FeeTier>,
+}
+
+impl FeeSchedule {
+    /// Creates a schedule with a single base tier.
+    pub fn new(maker_bps: i64, taker_bps: i64) -> Self {
+        Self { tiers: vec![FeeTier { min_volume: 0, maker_bps, taker_bps }] }
+    }
+
+    /// Adds a tier applied once an owner has traded at least `min_volume`.
+    ///
+    /// A tier with the same `min_volume` replaces the existing one.
+    pub fn with_tier(mut self, min_volume: u128, maker_bps: i64, taker_bps: i64) -> Self {
+        self.tiers.retain(|t| t.min_volume != min_volume);
+        self.tiers.push(FeeTier { min_volume, maker_bps, taker_bps });
+        self.tiers.sort_by_key(|t| t.min_volume);
+        self
+    }
+
+    /// All tiers, sorted by ascending `min_volume`.
+    pub fn tiers(&self) -> &[FeeTier] {
+        &self.tiers
+    }
+
+    /// Returns the tier that applies to the given traded volume.
+    pub fn tier(&self, volume: u128) -> FeeTier {
+        self.tiers.iter().rev().find(|t| t.min_volume <= volume).copied().unwrap_or(FeeTier {
+            min_volume: 0,
+            maker_bps: 0,
+            taker_bps: 0,
+        })
+    }
+
+    /// Highest positive rate of the schedule, used to reserve enough funds to
+    /// pay the fees of a buy order whatever tier it ends up being charged at.
+    pub(crate) fn max_bps(&self) -> u64 {
+        self.tiers
+            .iter()
+            .flat_map(|t| [t.maker_bps, t.taker_bps])
+            .max()
+            .map_or(0, |bps| bps.max(0) as u64)
+    }
+}
+
+/// Computes the fee on `notional` at `bps`, rounded up.
+pub(crate) fn fee(notional: u128, bps: i64) -> i128 {
+    let amount = notional as i128 * i128::from(bps);
+    let fee = amount.div_euclid(10_000);
+    if amount.rem_euclid(10_000) == 0 {
+        fee
+    } else {
+        fee + 1
+    }
+}
+
+/// Fee schedule together with the traded volume of every owner.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Fees {
+    pub(crate) schedule: FeeSchedule,
+    pub(crate) volumes: HashMap<OwnerId, u128>,
+}
+
+impl Fees {
+    pub(crate) fn new(schedule: FeeSchedule) -> Self {
+        Self { schedule, volumes: HashMap::new() }
+    }
+
+    /// Computes the `(maker_fee, taker_fee)` of a fill and records its notional
+    /// in the traded volume of both owners.
+    pub(crate) fn charge(
+        &mut self,
+        maker: Option<OwnerId>,
+        taker: Option<OwnerId>,
+        notional: u128,
+    ) -> (i128, i128) {
+        let maker_fee = fee(notional, self.schedule.tier(self.volume(maker)).maker_bps);
+        let taker_fee = fee(notional, self.schedule.tier(self.volume(taker)).taker_bps);
+        for owner in [maker, taker].into_iter().flatten() {
+            let volume = self.volumes.entry(owner).or_default();
+            *volume = volume.saturating_add(notional);
+        }
+        (maker_fee, taker_fee)
+    }
+
+    /// Computes the maker fee of a fill in which both orders were resting, as
+    /// in a call auction, and records its notional in the owner's traded volume.
+    pub(crate) fn charge_maker(&mut self, owner: Option<OwnerId>, notional: u128) -> i128 {
+        let maker_fee = fee(notional, self.schedule.tier(self.volume(owner)).maker_bps);
+        if let Some(owner) = owner {
+            let volume = self.volumes.entry(owner).or_default();
+            *volume = volume.saturating_add(notional);
+        }
+        maker_fee
+    }
+
+    pub(crate) fn volume(&self, owner: Option<OwnerId>) -> u128 {
+        owner.and_then(|o| self.volumes.get(&o).copied()).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_rounding() {
+        assert_eq!(fee(10_000, 5), 5);
+        assert_eq!(fee(10_001, 5), 6);
+        assert_eq!(fee(10_000, -2), -2);
+        // rebates are rounded towards zero
+        assert_eq!(fee(10_001, -2), -2);
+        assert_eq!(fee(1, -2), 0);
+        assert_eq!(fee(1_000, 0), 0);
+    }
+
+    #[test]
+    fn test_schedule_tiers() {
+        let schedule = FeeSchedule::new(2, 5).with_tier(1_000, -1, 3).with_tier(500, 1, 4);
+        assert_eq!(schedule.tiers().len(), 3);
+        assert_eq!(schedule.tier(0).taker_bps, 5);
+        assert_eq!(schedule.tier(499).taker_bps, 5);
+        assert_eq!(schedule.tier(500).taker_bps, 4);
+        assert_eq!(schedule.tier(5_000).maker_bps, -1);
+        assert_eq!(schedule.max_bps(), 5);
+        assert_eq!(FeeSchedule::new(-1, 0).max_bps(), 0);
+
+        // replace the base tier
+        let schedule = schedule.with_tier(0, 0, 0);
+        assert_eq!(schedule.tier(0).taker_bps, 0);
+        assert_eq!(schedule.tiers().len(), 3);
+    }
+
+    #[test]
+    fn test_charge_tracks_volume() {
+        let maker = OwnerId(1);
+        let taker = OwnerId(2);
+        let mut fees = Fees::new(FeeSchedule::new(2, 5).with_tier(100_000, -1, 3));
+
+        assert_eq!(fees.charge(Some(maker), Some(taker), 100_000), (20, 50));
+        assert_eq!(fees.volume(Some(maker)), 100_000);
+        assert_eq!(fees.volume(Some(taker)), 100_000);
+        assert_eq!(fees.volume(None), 0);
+
+        // both owners reached the second tier
+        assert_eq!(fees.charge(Some(maker), Some(taker), 100_000), (-10, 30));
+        // orders without owner pay the base tier
+        assert_eq!(fees.charge(None, None, 100_000), (20, 50));
+        assert_eq!(fees.charge_maker(Some(maker), 100_000), -10);
+        assert_eq!(fees.volume(Some(maker)), 300_000);
+    }
+}
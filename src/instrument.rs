@@ -0,0 +1,337 @@
+//! Static trading rules of the instrument traded by the order book.
+//!
+//! The specification is configured once through
+//! [`OrderBookBuilder::with_instrument`](crate::OrderBookBuilder::with_instrument)
+//! and every order entry point (market, limit, modify and the auction-only
+//! orders) validates the submitted order against it.
+//!
+//! The tick size can depend on the price through a [`TickTable`], as in the
+//! MiFID II tick size regime or the price tiers of many Asian exchanges. It is
+//! used to validate prices, to cap prices to a price band and to group the
+//! aggregated depth of
+//! [`OrderBook::grouped_depth`](crate::OrderBook::grouped_depth) by ticks.
+
+use crate::{
+    error::{make_error, ErrorType, Result},
+    order::{Price, Quantity},
+    risk::notional,
+};
+
+/// Tick size applied from a given price.
+///
+/// # Fields
+/// - `min_price`: Lowest price of the tier
+/// - `tick_size`: Tick size of the prices in the tier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickTier {
+    pub min_price: Price,
+    pub tick_size: Price,
+}
+
+/// A price-dependent tick size table.
+///
+/// The tick size of a price is the one of the tier with the highest
+/// `min_price` not above it. Every tier should start at a multiple of its own
+/// tick size, so that the first price of a tier is a valid price.
+///
+/// # Example
+/// ```rust
+/// use rust_order_book::TickTable;
+///
+/// // tick of 1 below 1_000, 5 up to 10_000 and 10 above
+/// let table = TickTable::new(1).with_tier(1_000, 5).with_tier(10_000, 10);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickTable {
+    tiers: Vec<TickTier>,
+}
+
+impl TickTable {
+    /// Creates a table with a single tick size for every price.
+    pub fn new(tick_size: u64) -> Self {
+        Self { tiers: vec![TickTier { min_price: Price(0), tick_size: Price(tick_size) }] }
+    }
+
+    /// Adds a tier applied from `min_price` upwards.
+    ///
+    /// A tier with the same `min_price` replaces the existing one.
+    pub fn with_tier(mut self, min_price: u64, tick_size: u64) -> Self {
+        self.tiers.retain(|t| t.min_price != Price(min_price));
+        self.tiers.push(TickTier { min_price: Price(min_price), tick_size: Price(tick_size) });
+        self.tiers.sort_by_key(|t| t.min_price);
+        self
+    }
+
+    /// All tiers, sorted by ascending `min_price`.
+    pub fn tiers(&self) -> &[TickTier] {
+        &self.tiers
+    }
+
+    /// Returns the tick size that applies to `price`, zero when no tick size applies.
+    pub fn tick_size(&self, price: Price) -> Price {
+        self.tier(price).map_or(Price(0), |t| t.tick_size)
+    }
+
+    /// Returns the tier that applies to `price`.
+    fn tier(&self, price: Price) -> Option<&TickTier> {
+        self.tiers.iter().rev().find(|t| t.min_price <= price)
+    }
+
+    /// Returns the first price of the tier above the one of `price`, if any.
+    fn next_tier(&self, price: Price) -> Option<Price> {
+        self.tiers.iter().find(|t| t.min_price > price).map(|t| t.min_price)
+    }
+}
+
+/// Tick size, lot size and size limits of an instrument.
+///
+/// Every rule is optional: a `None` value disables the corresponding check.
+///
+/// # Fields
+/// - `tick_size`: Prices must be a multiple of the tick size of their [`TickTable`] tier
+/// - `lot_size`: Quantities must be a multiple of the lot size
+/// - `min_qty`: Minimum quantity of a single order
+/// - `max_qty`: Maximum quantity of a single order
+/// - `min_notional`: Minimum notional (`price × quantity`) of a limit order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstrumentSpec {
+    pub tick_size: Option<TickTable>,
+    pub lot_size: Option<Quantity>,
+    pub min_qty: Option<Quantity>,
+    pub max_qty: Option<Quantity>,
+    pub min_notional: Option<u128>,
+}
+
+impl InstrumentSpec {
+    /// Sets a single tick size for every price.
+    pub fn with_tick_size(mut self, tick_size: u64) -> Self {
+        self.tick_size = Some(TickTable::new(tick_size));
+        self
+    }
+
+    /// Sets a price-dependent tick size table.
+    pub fn with_tick_table(mut self, table: TickTable) -> Self {
+        self.tick_size = Some(table);
+        self
+    }
+
+    /// Returns the tick size that applies to `price`, if a tick size is configured.
+    pub fn tick_size_at(&self, price: Price) -> Option<Price> {
+        self.tick_size.as_ref().map(|table| table.tick_size(price)).filter(|t| t.value() > 0)
+    }
+
+    /// Sets the lot size.
+    pub fn with_lot_size(mut self, lot_size: u64) -> Self {
+        self.lot_size = Some(Quantity(lot_size));
+        self
+    }
+
+    /// Sets the minimum quantity of a single order.
+    pub fn with_min_qty(mut self, quantity: u64) -> Self {
+        self.min_qty = Some(Quantity(quantity));
+        self
+    }
+
+    /// Sets the maximum quantity of a single order.
+    pub fn with_max_qty(mut self, quantity: u64) -> Self {
+        self.max_qty = Some(Quantity(quantity));
+        self
+    }
+
+    /// Sets the minimum notional (`price × quantity`) of a limit order.
+    pub fn with_min_notional(mut self, notional: u128) -> Self {
+        self.min_notional = Some(notional);
+        self
+    }
+
+    /// Checks the quantity of an order against the lot size and size limits.
+    pub(crate) fn validate_quantity(&self, quantity: Quantity) -> Result<()> {
+        if self
+            .lot_size
+            .is_some_and(|lot| lot.value() > 0 && !quantity.value().is_multiple_of(lot.value()))
+        {
+            return Err(make_error(ErrorType::QuantityNotMultipleOfLot));
+        }
+        if self.min_qty.is_some_and(|min| quantity < min) {
+            return Err(make_error(ErrorType::QuantityBelowMinimum));
+        }
+        if self.max_qty.is_some_and(|max| quantity > max) {
+            return Err(make_error(ErrorType::QuantityAboveMaximum));
+        }
+        Ok(())
+    }
+
+    /// Checks a limit order against every rule of the specification.
+    pub(crate) fn validate_limit(&self, price: Price, quantity: Quantity) -> Result<()> {
+        if !self.is_on_tick(price) {
+            return Err(make_error(ErrorType::PriceNotMultipleOfTick));
+        }
+        self.validate_quantity(quantity)?;
+        if self.min_notional.is_some_and(|min| notional(price, quantity) < min) {
+            return Err(make_error(ErrorType::NotionalBelowMinimum));
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if `price` is a multiple of its tick size.
+    pub(crate) fn is_on_tick(&self, price: Price) -> bool {
+        self.tick_size_at(price).is_none_or(|tick| price.value().is_multiple_of(tick.value()))
+    }
+
+    /// Rounds `price` down to its tick size.
+    pub(crate) fn round_down(&self, price: Price) -> Price {
+        match self.tick_size_at(price) {
+            Some(tick) => Price(price.value() - price.value() % tick.value()),
+            None => price,
+        }
+    }
+
+    /// Rounds `price` up to its tick size, stopping at the first price of the
+    /// next tier and saturating to the highest valid price.
+    pub(crate) fn round_up(&self, price: Price) -> Price {
+        let (Some(table), Some(tick)) = (self.tick_size.as_ref(), self.tick_size_at(price)) else {
+            return price;
+        };
+        let down = self.round_down(price);
+        if down == price {
+            return price;
+        }
+        let up = down.value().checked_add(tick.value()).map_or(down, Price);
+        table.next_tier(price).map_or(up, |next| up.min(next))
+    }
+
+    /// Rounds `price` down to a bucket of `ticks` ticks. Buckets are counted
+    /// from the first price of the tier of `price`, or from zero with a tick of
+    /// `1` when no tick size is configured.
+    pub(crate) fn round_down_ticks(&self, price: Price, ticks: u64) -> Price {
+        let (start, width) = self.bucket(price, ticks);
+        Price(price.value() - (price.value() - start) % width)
+    }
+
+    /// Rounds `price` up to a bucket of `ticks` ticks, stopping at the first
+    /// price of the next tier.
+    pub(crate) fn round_up_ticks(&self, price: Price, ticks: u64) -> Price {
+        let down = self.round_down_ticks(price, ticks);
+        if down == price {
+            return price;
+        }
+        let up = Price(down.value().saturating_add(self.bucket(price, ticks).1));
+        match self.tick_size.as_ref().and_then(|table| table.next_tier(price)) {
+            Some(next) => up.min(next),
+            None => up,
+        }
+    }
+
+    /// Returns the start and width of the buckets of `ticks` ticks around `price`.
+    fn bucket(&self, price: Price, ticks: u64) -> (u64, u64) {
+        self.tick_size
+            .as_ref()
+            .and_then(|table| table.tier(price))
+            .filter(|tier| tier.tick_size.value() > 0)
+            .map_or((0, ticks), |tier| {
+                (tier.min_price.value(), tier.tick_size.value().saturating_mul(ticks))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(result: Result<()>) -> u32 {
+        result.unwrap_err().code
+    }
+
+    #[test]
+    fn test_validate_limit() {
+        let spec = InstrumentSpec::default()
+            .with_tick_size(5)
+            .with_lot_size(10)
+            .with_min_qty(20)
+            .with_max_qty(1000)
+            .with_min_notional(5000);
+
+        assert!(spec.validate_limit(Price(250), Quantity(20)).is_ok());
+        assert_eq!(
+            code(spec.validate_limit(Price(251), Quantity(20))),
+            make_error(ErrorType::PriceNotMultipleOfTick).code
+        );
+        assert_eq!(
+            code(spec.validate_limit(Price(250), Quantity(25))),
+            make_error(ErrorType::QuantityNotMultipleOfLot).code
+        );
+        assert_eq!(
+            code(spec.validate_limit(Price(250), Quantity(10))),
+            make_error(ErrorType::QuantityBelowMinimum).code
+        );
+        assert_eq!(
+            code(spec.validate_limit(Price(250), Quantity(1010))),
+            make_error(ErrorType::QuantityAboveMaximum).code
+        );
+        assert_eq!(
+            code(spec.validate_limit(Price(245), Quantity(20))),
+            make_error(ErrorType::NotionalBelowMinimum).code
+        );
+
+        // no rule configured
+        assert!(InstrumentSpec::default().validate_limit(Price(1), Quantity(1)).is_ok());
+    }
+
+    #[test]
+    fn test_rounding() {
+        let spec = InstrumentSpec::default().with_tick_size(5);
+        assert_eq!(spec.round_down(Price(104)), Price(100));
+        assert_eq!(spec.round_up(Price(101)), Price(105));
+        assert_eq!(spec.round_up(Price(105)), Price(105));
+        // no tick above u64::MAX - 1 with a tick size of 7
+        let spec = InstrumentSpec::default().with_tick_size(7);
+        assert_eq!(spec.round_up(Price(u64::MAX)), Price(u64::MAX - 1));
+        assert_eq!(InstrumentSpec::default().round_up(Price(101)), Price(101));
+    }
+
+    #[test]
+    fn test_tick_table() {
+        let table = TickTable::new(1).with_tier(1000, 5).with_tier(10_000, 50).with_tier(1000, 10);
+        assert_eq!(table.tiers().len(), 3);
+        assert_eq!(table.tick_size(Price(999)), Price(1));
+        assert_eq!(table.tick_size(Price(1000)), Price(10));
+        assert_eq!(table.tick_size(Price(20_000)), Price(50));
+
+        let spec = InstrumentSpec::default().with_tick_table(table);
+        assert!(spec.validate_limit(Price(999), Quantity(1)).is_ok());
+        assert!(spec.validate_limit(Price(1010), Quantity(1)).is_ok());
+        assert!(spec.validate_limit(Price(1005), Quantity(1)).is_err());
+        assert!(spec.validate_limit(Price(10_020), Quantity(1)).is_err());
+
+        assert_eq!(spec.round_down(Price(1009)), Price(1000));
+        assert_eq!(spec.round_up(Price(1001)), Price(1010));
+        assert_eq!(spec.round_up(Price(9_995)), Price(10_000));
+        assert_eq!(spec.round_up(Price(10_001)), Price(10_050));
+
+        // a table starting above zero leaves the lower prices unconstrained
+        let spec = InstrumentSpec::default().with_tick_table(TickTable::new(0).with_tier(100, 5));
+        assert_eq!(spec.tick_size_at(Price(99)), None);
+        assert!(spec.validate_limit(Price(99), Quantity(1)).is_ok());
+        assert_eq!(spec.round_up(Price(99)), Price(99));
+    }
+
+    #[test]
+    fn test_tick_buckets() {
+        let table = TickTable::new(1).with_tier(1000, 5).with_tier(10_000, 50);
+        let spec = InstrumentSpec::default().with_tick_table(table);
+        // buckets of 3 ticks start at the first price of the tier
+        assert_eq!(spec.round_down_ticks(Price(1014), 3), Price(1000));
+        assert_eq!(spec.round_up_ticks(Price(1005), 3), Price(1015));
+        assert_eq!(spec.round_up_ticks(Price(1015), 3), Price(1015));
+        // and never spill over the next tier
+        assert_eq!(spec.round_down_ticks(Price(9_995), 3), Price(9_985));
+        assert_eq!(spec.round_up_ticks(Price(9_995), 3), Price(10_000));
+        assert_eq!(spec.round_up_ticks(Price(999), 4), Price(1000));
+
+        // without a tick size, buckets are counted in units of price
+        let spec = InstrumentSpec::default();
+        assert_eq!(spec.round_down_ticks(Price(1013), 3), Price(1011));
+        assert_eq!(spec.round_up_ticks(Price(1013), 3), Price(1014));
+        assert_eq!(spec.round_up_ticks(Price(u64::MAX), 2), Price(u64::MAX));
+    }
+}
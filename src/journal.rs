@@ -3,10 +3,35 @@
 //! Journaling allows tracking a chronological log of operations
 //! (such as order submissions, cancellations, modifications)
 //! for replay, audit, or recovery purposes.
+//!
+//! # Schema
+//!
+//! Journal logs, execution reports and depths serialize with serde. Newtype
+//! IDs, prices and quantities are plain numbers and enums are snake case
+//! strings, except [`crate::TimeInForce`] which is upper case. The input of an
+//! operation is tagged with its type, see [`OrderOptions`]:
+//!
+//! ```json
+//! {"op_id":1,"ts":1700000000000,"op":"limit","o":{"type":"limit","data":{"side":"buy","quantity":5,"price":100,"time_in_force":null,"post_only":null,"owner":null,"session":null,"persist":false}}}
+//! {"op_id":2,"ts":1700000000001,"op":"cancel","o":{"type":"cancel","data":0}}
+//! ```
+//!
+//! Optional fields can be omitted when deserializing. With the `json` feature,
+//! `JournalWriter` and `JournalReader` persist a journal as JSON Lines, one log
+//! per line.
+
+#[cfg(feature = "json")]
+use std::io::{self, BufRead, Write};
 
 use crate::{
-    enums::{JournalOp, OrderOptions},
-    order::{LimitOrder, OrderId, Price},
+    accounts::Account,
+    candles::Candle,
+    enums::{JournalOp, OrderOptions, TradingPhase},
+    order::{LimitOrder, OrderId, OwnerId, Price},
+    positions::Position,
+    throttle::ThrottleActivity,
+    trade::{Trade, TradeId},
+    Side,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap, VecDeque};
@@ -21,7 +46,7 @@ use std::collections::{BTreeMap, HashMap, VecDeque};
 /// - `ts`: Timestamp of when the operation was recorded (in milliseconds since epoch).
 /// - `op`: The type of operation performed (e.g., market, limit, cancel).
 /// - `o`: The payload or input associated with the operation.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct JournalLog {
     pub op_id: u64,
     pub ts: i64,
@@ -37,4 +62,333 @@ pub struct Snapshot {
     pub last_op: u64,
     pub next_order_id: OrderId,
     pub ts: i64,
+    /// Price of the last trade executed by the book
+    #[serde(default)]
+    pub last_trade_price: Option<Price>,
+    /// External reference price used by the price band
+    #[serde(default)]
+    pub reference_price: Option<Price>,
+    /// Balances of every owner, if accounts are enabled
+    #[serde(default)]
+    pub accounts: Option<HashMap<OwnerId, Account>>,
+    /// Positions of every owner, if position tracking is enabled
+    #[serde(default)]
+    pub positions: Option<HashMap<OwnerId, Position>>,
+    /// Traded volume of every owner, if fees are enabled
+    #[serde(default)]
+    pub fee_volumes: Option<HashMap<OwnerId, u128>>,
+    /// Throttling counters of every owner, if throttling is enabled
+    #[serde(default)]
+    pub throttle: Option<HashMap<OwnerId, ThrottleActivity>>,
+    /// Current trading phase
+    #[serde(default)]
+    pub phase: TradingPhase,
+    /// Auction-only orders waiting for their auction
+    #[serde(default)]
+    pub auction_orders: HashMap<OrderId, LimitOrder>,
+    /// Sequence number of the last level-2 update
+    #[serde(default)]
+    pub depth_sequence: u64,
+    /// Sequence number of the last market-by-order message
+    #[serde(default)]
+    pub l3_sequence: u64,
+    /// Sequence number of the last top of book change
+    #[serde(default)]
+    pub bbo_sequence: u64,
+    /// ID of the next trade
+    #[serde(default)]
+    pub next_trade_id: TradeId,
+    /// Recent trades, oldest first
+    #[serde(default)]
+    pub trades: VecDeque<Trade>,
+    /// Open candles, if candle aggregation is enabled
+    #[serde(default)]
+    pub candles: Option<Vec<Candle>>,
+    /// Sequence number of the last conflated level-2 batch
+    #[serde(default)]
+    pub conflated_sequence: u64,
+}
+
+/// Writes journal logs as JSON Lines.
+///
+/// Requires the `json` feature.
+///
+/// # Example
+/// ```rust
+/// use rust_order_book::{JournalReader, JournalWriter, LimitOrderOptions, OrderBookBuilder, Side};
+///
+/// let mut ob = OrderBookBuilder::new("BTCUSD").with_journaling(true).build();
+/// let report = ob.limit(LimitOrderOptions::new(Side::Buy, 5, 100, None, None)).unwrap();
+///
+/// let mut writer = JournalWriter::new(Vec::new());
+/// writer.write(report.log.as_ref().unwrap()).unwrap();
+///
+/// let logs = JournalReader::new(writer.into_inner().as_slice()).collect::<Result<Vec<_>, _>>();
+/// assert_eq!(logs.unwrap(), vec![report.log.unwrap()]);
+/// ```
+#[cfg(feature = "json")]
+#[derive(Debug)]
+pub struct JournalWriter<W: Write> {
+    inner: W,
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> JournalWriter<W> {
+    /// Creates a writer appending to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Writes a log followed by a newline.
+    ///
+    /// # Errors
+    /// Returns `Err` if the underlying writer fails.
+    pub fn write(&mut self, log: &JournalLog) -> io::Result<()> {
+        serde_json::to_writer(&mut self.inner, log)?;
+        self.inner.write_all(b"\n")
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// Reads journal logs from JSON Lines, skipping empty lines.
+///
+/// Requires the `json` feature.
+///
+/// The reader is an iterator over the logs, ready for
+/// [`OrderBook::replay_logs`](crate::OrderBook::replay_logs), see
+/// [`JournalWriter`].
+#[cfg(feature = "json")]
+#[derive(Debug)]
+pub struct JournalReader<R: BufRead> {
+    lines: io::Lines<R>,
+}
+
+#[cfg(feature = "json")]
+impl<R: BufRead> JournalReader<R> {
+    /// Creates a reader of the lines of `inner`.
+    pub fn new(inner: R) -> Self {
+        Self { lines: inner.lines() }
+    }
+}
+
+#[cfg(feature = "json")]
+impl<R: BufRead> Iterator for JournalReader<R> {
+    type Item = io::Result<JournalLog>;
+
+    /// Returns the next log, or an [`io::ErrorKind::InvalidData`] error if a
+    /// line is not a valid log.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            if !line.trim().is_empty() {
+                return Some(serde_json::from_str(&line).map_err(io::Error::from));
+            }
+        }
+    }
+}
+
+impl Snapshot {
+    /// Compares the orders and the price levels of this snapshot with `other`.
+    ///
+    /// Resting and auction-only orders are compared by ID. Only the structure
+    /// of the book is compared: counters, balances and the other state of the
+    /// snapshots are ignored.
+    ///
+    /// # Example
+    /// ```rust
+    /// use rust_order_book::{LimitOrderOptions, OrderBookBuilder, Side};
+    ///
+    /// let mut ob = OrderBookBuilder::new("BTCUSD").build();
+    /// let before = ob.snapshot();
+    /// let _ = ob.limit(LimitOrderOptions::new(Side::Buy, 1, 100, None, None));
+    /// let diff = before.diff(&ob.snapshot());
+    /// assert_eq!(diff.orders_added.len(), 1);
+    /// assert_eq!(diff.levels_added.len(), 1);
+    /// ```
+    pub fn diff(&self, other: &Snapshot) -> SnapshotDiff {
+        let mut diff = SnapshotDiff::default();
+        let find = |snapshot: &Snapshot, id: &OrderId| {
+            snapshot.orders.get(id).or_else(|| snapshot.auction_orders.get(id)).copied()
+        };
+        for (id, order) in self.orders.iter().chain(self.auction_orders.iter()) {
+            match find(other, id) {
+                None => diff.orders_removed.push(*id),
+                Some(o) if o != *order => diff.orders_changed.push(*id),
+                Some(_) => {}
+            }
+        }
+        for id in other.orders.keys().chain(other.auction_orders.keys()) {
+            if find(self, id).is_none() {
+                diff.orders_added.push(*id);
+            }
+        }
+        for ids in [&mut diff.orders_added, &mut diff.orders_removed, &mut diff.orders_changed] {
+            ids.sort_by_key(|id| id.0);
+        }
+
+        for (side, levels, other_levels) in
+            [(Side::Buy, &self.bids, &other.bids), (Side::Sell, &self.asks, &other.asks)]
+        {
+            for (price, queue) in levels {
+                match other_levels.get(price) {
+                    None => diff.levels_removed.push((side, *price)),
+                    Some(q) if q != queue => diff.queues_changed.push((side, *price)),
+                    Some(_) => {}
+                }
+            }
+            for price in other_levels.keys().filter(|p| !levels.contains_key(p)) {
+                diff.levels_added.push((side, *price));
+            }
+        }
+        diff
+    }
+}
+
+/// Structural differences between two snapshots, see [`Snapshot::diff`].
+///
+/// Order IDs are sorted in ascending order and price levels by side, bids
+/// first, then by ascending price.
+///
+/// # Fields
+/// - `orders_added`: Orders only present in the other snapshot
+/// - `orders_removed`: Orders only present in this snapshot
+/// - `orders_changed`: Orders present in both snapshots with a different state
+/// - `levels_added`: Price levels only present in the other snapshot
+/// - `levels_removed`: Price levels only present in this snapshot
+/// - `queues_changed`: Price levels present in both snapshots with different
+///   orders or a different time priority
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    pub orders_added: Vec<OrderId>,
+    pub orders_removed: Vec<OrderId>,
+    pub orders_changed: Vec<OrderId>,
+    pub levels_added: Vec<(Side, Price)>,
+    pub levels_removed: Vec<(Side, Price)>,
+    pub queues_changed: Vec<(Side, Price)>,
+}
+
+impl SnapshotDiff {
+    /// Returns `true` if both snapshots have the same orders and price levels.
+    pub fn is_empty(&self) -> bool {
+        self.orders_added.is_empty()
+            && self.orders_removed.is_empty()
+            && self.orders_changed.is_empty()
+            && self.levels_added.is_empty()
+            && self.levels_removed.is_empty()
+            && self.queues_changed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        accounts::Asset, order::Quantity, ExecutionReport, LimitOrderOptions, MarketOrderOptions,
+        OrderBookBuilder,
+    };
+    use serde_json::{from_str, to_string};
+
+    fn log(op: JournalOp, o: OrderOptions) -> JournalLog {
+        JournalLog { op_id: 1, ts: 1_700_000_000_000, op, o }
+    }
+
+    // The limit log is the schema example of the module documentation
+    #[test]
+    fn test_journal_log_schema() {
+        let limit = log(
+            JournalOp::Limit,
+            OrderOptions::Limit(LimitOrderOptions::new(Side::Buy, 5, 100, None, None)),
+        );
+        assert_eq!(
+            to_string(&limit).unwrap(),
+            r#"{"op_id":1,"ts":1700000000000,"op":"limit","o":{"type":"limit","data":{"side":"buy","quantity":5,"price":100,"time_in_force":null,"post_only":null,"owner":null,"session":null,"persist":false}}}"#
+        );
+        // optional fields can be omitted
+        let minimal = r#"{"op_id":1,"ts":1700000000000,"op":"limit","o":{"type":"limit","data":{"side":"buy","quantity":5,"price":100}}}"#;
+        assert_eq!(from_str::<JournalLog>(minimal).unwrap(), limit);
+
+        let cancel = log(JournalOp::Cancel, OrderOptions::Cancel(OrderId(3)));
+        assert_eq!(
+            to_string(&cancel).unwrap(),
+            r#"{"op_id":1,"ts":1700000000000,"op":"cancel","o":{"type":"cancel","data":3}}"#
+        );
+        let uncross = log(JournalOp::Uncross, OrderOptions::Uncross);
+        assert!(to_string(&uncross).unwrap().ends_with(r#""o":{"type":"uncross"}}"#));
+
+        for o in [
+            OrderOptions::Market(MarketOrderOptions::new(Side::Sell, 2).with_owner(OwnerId(7))),
+            OrderOptions::Modify { id: OrderId(1), price: Some(Price(10)), quantity: None },
+            OrderOptions::SetReferencePrice(Price(100)),
+            OrderOptions::Deposit { owner: OwnerId(1), asset: Asset::Quote, amount: u128::MAX },
+            OrderOptions::Withdraw { owner: OwnerId(1), asset: Asset::Base, amount: 5 },
+            OrderOptions::SetPhase(TradingPhase::Halted),
+            OrderOptions::MarketOnClose(MarketOrderOptions::new(Side::Buy, 1)),
+            OrderOptions::LimitOnOpen(LimitOrderOptions::new(Side::Sell, 1, 99, None, Some(true))),
+        ] {
+            let log = log(JournalOp::Market, o);
+            assert_eq!(from_str::<JournalLog>(&to_string(&log).unwrap()).unwrap(), log);
+        }
+    }
+
+    #[test]
+    fn test_report_serialization() {
+        let mut ob = OrderBookBuilder::new("BTCUSD").with_journaling(true).build();
+        assert!(ob.limit(LimitOrderOptions::new(Side::Sell, 2, 100, None, None)).is_ok());
+        let report = ob.market(MarketOrderOptions::new(Side::Buy, 3)).unwrap();
+        assert_eq!(report.fills.len(), 1);
+        let json = to_string(&report).unwrap();
+        assert_eq!(from_str::<ExecutionReport>(&json).unwrap(), report);
+
+        assert!(ob.limit(LimitOrderOptions::new(Side::Buy, 2, 99, None, None)).is_ok());
+        let depth = ob.depth(None);
+        assert_eq!(
+            to_string(&depth).unwrap(),
+            r#"{"asks":[],"bids":[{"price":99,"quantity":2,"order_count":1,"cumulative_qty":2,"cumulative_notional":198}]}"#
+        );
+        assert_eq!(from_str::<crate::Depth>(&to_string(&depth).unwrap()).unwrap(), depth);
+        assert_eq!(depth.bids[0].quantity, Quantity(2));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_lines_journal() {
+        let mut ob = OrderBookBuilder::new("BTCUSD").with_journaling(true).build();
+        let mut writer = JournalWriter::new(Vec::new());
+        let reports = [
+            ob.limit(LimitOrderOptions::new(Side::Sell, 5, 101, None, None)),
+            ob.limit(LimitOrderOptions::new(Side::Buy, 3, 99, None, None)),
+            ob.market(MarketOrderOptions::new(Side::Buy, 2)),
+            ob.modify(OrderId(1), Some(Price(100)), None),
+            ob.cancel(OrderId(0)),
+        ];
+        for report in reports {
+            writer.write(report.unwrap().log.as_ref().unwrap()).unwrap();
+        }
+        writer.flush().unwrap();
+        let mut bytes = writer.into_inner();
+        assert_eq!(bytes.iter().filter(|b| **b == b'\n').count(), 5);
+        bytes.extend_from_slice(b"\n");
+
+        let logs: Vec<JournalLog> =
+            JournalReader::new(bytes.as_slice()).collect::<io::Result<_>>().unwrap();
+        assert_eq!(logs.len(), 5);
+        let replayed = OrderBookBuilder::new("BTCUSD").with_replay_logs(logs).build();
+        assert_eq!(replayed.depth(None), ob.depth(None));
+
+        let mut invalid = JournalReader::new(&b"{\"op_id\":1}\n"[..]);
+        assert_eq!(invalid.next().unwrap().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(invalid.next().is_none());
+    }
 }
@@ -1,16 +1,47 @@
+mod accounts;
+mod auction;
 mod book;
 mod builder;
+mod candles;
+mod conflation;
 mod enums;
 mod error;
+mod events;
+mod fees;
+mod instrument;
 mod journal;
+mod market_data;
 mod order;
+mod positions;
 mod report;
+mod risk;
+mod throttle;
+mod trade;
 mod utils;
 
-pub use book::{Depth, OrderBook, OrderBookOptions};
+pub use accounts::{Account, Asset, Balance};
+pub use auction::IndicativeAuction;
+pub use book::{Depth, DepthGrouping, DepthLevel, OrderBook, OrderBookOptions};
 pub use builder::OrderBookBuilder;
-pub use enums::{OrderStatus, OrderType, Side, TimeInForce};
+pub use candles::{Candle, CandleInterval};
+pub use conflation::{ConflatedDepthUpdate, ConflationPolicy};
+pub use enums::{OrderStatus, OrderType, Side, TimeInForce, TradingPhase};
 pub use error::OrderBookError;
-pub use journal::{JournalLog, Snapshot};
-pub use order::{LimitOrderOptions, MarketOrderOptions, OrderId, Price, Quantity};
-pub use report::{ExecutionReport, FillReport};
+pub use events::{OrderBookEvent, OrderBookListener};
+pub use fees::{FeeSchedule, FeeTier};
+pub use instrument::{InstrumentSpec, TickTable, TickTier};
+pub use journal::{JournalLog, Snapshot, SnapshotDiff};
+#[cfg(feature = "json")]
+pub use journal::{JournalReader, JournalWriter};
+pub use market_data::{BboUpdate, DepthSnapshot, DepthUpdate, L3Message, L3Update, TopOfBook};
+pub use order::{
+    LimitOrderOptions, MarketOrderOptions, OrderId, OwnerId, Price, Quantity, SessionId,
+};
+pub use positions::Position;
+pub use report::{ExecutionReport, FillReport, UncrossReport};
+pub use risk::{
+    InterruptionPhase, InterruptionRemainder, PriceBand, PriceBandAction, ReferencePrice,
+    RiskLimits, VolatilityInterruption,
+};
+pub use throttle::{ThrottleActivity, ThrottleLimits};
+pub use trade::{Trade, TradeId};
@@ -0,0 +1,99 @@
+//! Market data published by the order book.
+//!
+//! The incremental level-2 feed is delivered to the registered
+//! [`OrderBookListener`](crate::OrderBookListener)s as
+//! [`OrderBookEvent::DepthUpdate`](crate::OrderBookEvent::DepthUpdate) events.
+//! Every update carries the new total quantity of a price level and a sequence
+//! number incremented by one on every update, so that a consumer can detect a
+//! gap. A consumer bootstraps from
+//! [`OrderBook::depth_snapshot`](crate::OrderBook::depth_snapshot), then applies
+//! the updates with a higher sequence number.
+//!
+//! The market-by-order (level-3) feed is delivered as
+//! [`OrderBookEvent::L3Update`](crate::OrderBookEvent::L3Update) events, with a
+//! sequence number of its own. Starting from an empty book, the feed alone is
+//! enough to rebuild every resting order, in time priority. The owner of the
+//! orders is only disclosed when order attribution is enabled with
+//! [`OrderBookBuilder::with_order_attribution`](crate::OrderBookBuilder::with_order_attribution).
+//!
+//! Changes of the best bid or best ask, price or quantity, are delivered as
+//! [`OrderBookEvent::BboUpdate`](crate::OrderBookEvent::BboUpdate) events, at
+//! most one per operation.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    order::{OrderId, OwnerId, Price, Quantity},
+    Depth, Side,
+};
+
+/// New total quantity of a price level.
+///
+/// # Fields
+/// - `sequence`: Sequence number of the update
+/// - `side`: Side of the level
+/// - `price`: Price of the level
+/// - `quantity`: New total quantity of the level, zero if the level was removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepthUpdate {
+    pub sequence: u64,
+    pub side: Side,
+    pub price: Price,
+    pub quantity: Quantity,
+}
+
+/// A message of the market-by-order feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum L3Message {
+    /// An order was added at the back of the queue of its price level. `owner`
+    /// is `None` unless order attribution is enabled.
+    Add { id: OrderId, side: Side, price: Price, quantity: Quantity, owner: Option<OwnerId> },
+    /// Part of a resting order was canceled without losing its time priority.
+    /// The book does not reduce orders in place yet: modifications lose priority
+    /// and are reported as a [`L3Message::Replace`].
+    Reduce { id: OrderId, side: Side, price: Price, quantity: Quantity },
+    /// A resting order was executed for `quantity` at `price`. An order executed
+    /// for its whole open quantity leaves the book without a
+    /// [`L3Message::Delete`].
+    Execute { id: OrderId, side: Side, price: Price, quantity: Quantity },
+    /// A resting order was removed from the book with `quantity` still open.
+    Delete { id: OrderId, side: Side, price: Price, quantity: Quantity },
+    /// A resting order was replaced by the order `new_id`, added at the back of
+    /// the queue of `price` with `quantity` open.
+    Replace { id: OrderId, new_id: OrderId, side: Side, price: Price, quantity: Quantity },
+}
+
+/// A message of the market-by-order feed with its sequence number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct L3Update {
+    pub sequence: u64,
+    pub message: L3Message,
+}
+
+/// Best bid and best ask of the book, with the total quantity of their levels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TopOfBook {
+    pub bid: Option<(Price, Quantity)>,
+    pub ask: Option<(Price, Quantity)>,
+}
+
+/// A change of the top of the book.
+///
+/// # Fields
+/// - `sequence`: Sequence number of the change
+/// - `previous`: The top of the book before the operation
+/// - `current`: The top of the book after the operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BboUpdate {
+    pub sequence: u64,
+    pub previous: TopOfBook,
+    pub current: TopOfBook,
+}
+
+/// The depth of the book together with the sequence number of the last update
+/// it includes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepthSnapshot {
+    pub sequence: u64,
+    pub depth: Depth,
+}
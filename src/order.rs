@@ -5,7 +5,7 @@
 //! or [`LimitOrder`] directly.
 
 use crate::{
-    utils::{current_timestamp_millis, safe_add, safe_sub},
+    utils::{safe_add, safe_sub},
     OrderStatus, OrderType, Side, TimeInForce,
 };
 use serde::{Deserialize, Serialize};
@@ -22,6 +22,21 @@ impl AddAssign<u64> for OrderId {
     }
 }
 
+/// Identifier of the account (or participant) that owns an order.
+///
+/// Owners are optional: orders submitted without an owner are never subject
+/// to per-owner checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Hash, PartialOrd, Ord)]
+pub struct OwnerId(pub u64);
+
+/// Identifier of the session (for example a gateway connection) an order was
+/// submitted through.
+///
+/// All the resting orders of a session can be canceled at once with
+/// [`crate::OrderBook::close_session`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Hash, PartialOrd, Ord)]
+pub struct SessionId(pub u64);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, PartialOrd, Ord)]
 pub struct Price(pub u64);
 impl Price {
@@ -53,7 +68,7 @@ impl Div for Price {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, PartialOrd, Ord)]
 pub struct Quantity(pub u64);
 impl Quantity {
     pub fn value(self) -> u64 {
@@ -93,14 +108,23 @@ impl Sum for Quantity {
 /// # Fields
 /// - `side`: Buy or Sell
 /// - `quantity`: The total amount to trade
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// - `owner`: Optional owner of the order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MarketOrderOptions {
     pub side: Side,
     pub quantity: Quantity,
+    #[serde(default)]
+    pub owner: Option<OwnerId>,
 }
 impl MarketOrderOptions {
     pub fn new(side: Side, quantity: u64) -> Self {
-        Self { side, quantity: Quantity(quantity) }
+        Self { side, quantity: Quantity(quantity), owner: None }
+    }
+
+    /// Sets the owner of the order.
+    pub fn with_owner(mut self, owner: OwnerId) -> Self {
+        self.owner = Some(owner);
+        self
     }
 }
 
@@ -139,13 +163,24 @@ impl MarketOrder {
 /// - `price`: Limit price
 /// - `time_in_force`: Optional TIF setting (default: GTC)
 /// - `post_only`: Optional post-only flag (default: false)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// - `owner`: Optional owner of the order
+/// - `session`: Optional session the order belongs to
+/// - `persist`: If `true`, the order is not canceled when its session is closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LimitOrderOptions {
     pub side: Side,
     pub quantity: Quantity,
     pub price: Price,
+    #[serde(default)]
     pub time_in_force: Option<TimeInForce>,
+    #[serde(default)]
     pub post_only: Option<bool>,
+    #[serde(default)]
+    pub owner: Option<OwnerId>,
+    #[serde(default)]
+    pub session: Option<SessionId>,
+    #[serde(default)]
+    pub persist: bool,
 }
 impl LimitOrderOptions {
     pub fn new(
@@ -155,7 +190,34 @@ impl LimitOrderOptions {
         time_in_force: Option<TimeInForce>,
         post_only: Option<bool>,
     ) -> Self {
-        Self { side, quantity: Quantity(quantity), price: Price(price), time_in_force, post_only }
+        Self {
+            side,
+            quantity: Quantity(quantity),
+            price: Price(price),
+            time_in_force,
+            post_only,
+            owner: None,
+            session: None,
+            persist: false,
+        }
+    }
+
+    /// Sets the owner of the order.
+    pub fn with_owner(mut self, owner: OwnerId) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Sets the session of the order.
+    pub fn with_session(mut self, session: SessionId) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Keeps the order on the book when its session is closed.
+    pub fn with_persist(mut self, persist: bool) -> Self {
+        self.persist = persist;
+        self
     }
 }
 
@@ -179,10 +241,16 @@ pub struct LimitOrder {
     pub(crate) taker_qty: Quantity,
     pub(crate) maker_qty: Quantity,
     pub(crate) status: OrderStatus,
+    #[serde(default)]
+    pub(crate) owner: Option<OwnerId>,
+    #[serde(default)]
+    pub(crate) session: Option<SessionId>,
+    #[serde(default)]
+    pub(crate) persist: bool,
 }
 
 impl LimitOrder {
-    pub(crate) fn new(id: OrderId, options: LimitOrderOptions) -> LimitOrder {
+    pub(crate) fn new(id: OrderId, options: LimitOrderOptions, time: i64) -> LimitOrder {
         LimitOrder {
             id,
             side: options.side,
@@ -190,12 +258,15 @@ impl LimitOrder {
             executed_qty: Quantity(0),
             price: options.price,
             order_type: OrderType::Limit,
-            time: current_timestamp_millis(),
+            time,
             time_in_force: get_order_time_in_force(options.time_in_force),
             post_only: options.post_only.unwrap_or(false),
             taker_qty: Quantity(0),
             maker_qty: Quantity(0),
             status: OrderStatus::New,
+            owner: options.owner,
+            session: options.session,
+            persist: options.persist,
         }
     }
 
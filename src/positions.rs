@@ -0,0 +1,164 @@
+//! Per-owner position and PnL tracking.
+//!
+//! When enabled with
+//! [`OrderBookBuilder::with_positions`](crate::OrderBookBuilder::with_positions),
+//! every fill updates the net position, the cost basis and the realized PnL of
+//! both the maker and the taker, so that strategy accounting can never diverge
+//! from matching.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    order::{OwnerId, Price, Quantity},
+    risk::notional,
+    Side,
+};
+
+/// Net position of an owner.
+///
+/// All amounts are signed: a positive `quantity` is a long position and a
+/// negative one is a short position. PnL is expressed in quote units
+/// (`price × quantity`).
+///
+/// # Fields
+/// - `quantity`: Net position
+/// - `cost`: Cost basis of the open position, with the same sign as `quantity`
+/// - `realized_pnl`: PnL realized by reducing or closing the position
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub quantity: i128,
+    pub cost: i128,
+    pub realized_pnl: i128,
+}
+
+impl Position {
+    /// Average entry price of the open position, or `None` if the position is flat.
+    pub fn avg_entry_price(&self) -> Option<Price> {
+        if self.quantity == 0 {
+            return None;
+        }
+        let price = self.cost.unsigned_abs() / self.quantity.unsigned_abs();
+        Some(Price(u64::try_from(price).unwrap_or(u64::MAX)))
+    }
+
+    /// PnL of the open position marked at `price`.
+    pub fn unrealized_pnl(&self, price: Price) -> i128 {
+        self.quantity * i128::from(price.value()) - self.cost
+    }
+
+    /// Applies a fill of `quantity` at `price` on the given side. The `fee` paid
+    /// for the fill is deducted from the realized PnL.
+    pub(crate) fn apply(&mut self, side: Side, price: Price, quantity: Quantity, fee: i128) {
+        self.realized_pnl -= fee;
+        let sign = match side {
+            Side::Buy => 1,
+            Side::Sell => -1,
+        };
+        let qty = i128::from(quantity.value());
+        let value = notional(price, quantity) as i128;
+
+        if self.quantity == 0 || self.quantity.signum() == sign {
+            self.quantity += sign * qty;
+            self.cost += sign * value;
+            return;
+        }
+
+        // The fill reduces, closes or flips the position
+        let closing = qty.min(self.quantity.abs());
+        let cost_closed = self.cost * closing / self.quantity.abs();
+        let value_closed = self.quantity.signum() * closing * i128::from(price.value());
+        self.realized_pnl += value_closed - cost_closed;
+        self.cost -= cost_closed;
+        self.quantity += sign * closing;
+
+        let opening = qty - closing;
+        if opening > 0 {
+            self.quantity = sign * opening;
+            self.cost = sign * opening * i128::from(price.value());
+        }
+    }
+}
+
+/// Positions of every owner.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Positions {
+    pub(crate) positions: HashMap<OwnerId, Position>,
+}
+
+impl Positions {
+    pub(crate) fn get(&self, owner: OwnerId) -> Option<Position> {
+        self.positions.get(&owner).copied()
+    }
+
+    pub(crate) fn apply(
+        &mut self,
+        owner: OwnerId,
+        side: Side,
+        price: Price,
+        quantity: Quantity,
+        fee: i128,
+    ) {
+        self.positions.entry(owner).or_default().apply(side, price, quantity, fee);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_and_increase() {
+        let mut p = Position::default();
+        assert_eq!(p.avg_entry_price(), None);
+
+        p.apply(Side::Buy, Price(100), Quantity(10), 0);
+        p.apply(Side::Buy, Price(130), Quantity(5), 0);
+        assert_eq!(p.quantity, 15);
+        assert_eq!(p.cost, 1650);
+        assert_eq!(p.avg_entry_price(), Some(Price(110)));
+        assert_eq!(p.unrealized_pnl(Price(120)), 150);
+        assert_eq!(p.realized_pnl, 0);
+    }
+
+    #[test]
+    fn test_reduce_and_close_long() {
+        let mut p = Position::default();
+        p.apply(Side::Buy, Price(100), Quantity(10), 0);
+        p.apply(Side::Sell, Price(110), Quantity(4), 0);
+        assert_eq!(p.quantity, 6);
+        assert_eq!(p.realized_pnl, 40);
+        assert_eq!(p.avg_entry_price(), Some(Price(100)));
+
+        p.apply(Side::Sell, Price(90), Quantity(6), 0);
+        assert_eq!(p.quantity, 0);
+        assert_eq!(p.cost, 0);
+        assert_eq!(p.realized_pnl, -20);
+    }
+
+    #[test]
+    fn test_short_and_flip() {
+        let mut p = Position::default();
+        p.apply(Side::Sell, Price(100), Quantity(10), 0);
+        assert_eq!(p.quantity, -10);
+        assert_eq!(p.avg_entry_price(), Some(Price(100)));
+        assert_eq!(p.unrealized_pnl(Price(90)), 100);
+
+        // buy 15 @ 90: close the short with +100 and open a long of 5 @ 90
+        p.apply(Side::Buy, Price(90), Quantity(15), 0);
+        assert_eq!(p.realized_pnl, 100);
+        assert_eq!(p.quantity, 5);
+        assert_eq!(p.cost, 450);
+        assert_eq!(p.avg_entry_price(), Some(Price(90)));
+    }
+
+    #[test]
+    fn test_fees_reduce_realized_pnl() {
+        let mut p = Position::default();
+        p.apply(Side::Buy, Price(100), Quantity(10), 5);
+        p.apply(Side::Sell, Price(110), Quantity(10), -2);
+        assert_eq!(p.realized_pnl, 97);
+        assert_eq!(p.quantity, 0);
+    }
+}
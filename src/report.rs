@@ -5,9 +5,11 @@
 //!
 //! These types are used to track the outcome of submitted market or limit orders,
 //! including how much was executed, any remaining quantity, and the resulting trades.
+use serde::{Deserialize, Serialize};
+
 use crate::{
     journal::JournalLog,
-    order::{get_order_time_in_force, OrderId, Price, Quantity},
+    order::{get_order_time_in_force, OrderId, OwnerId, Price, Quantity},
     OrderStatus, OrderType, Side, TimeInForce,
 };
 
@@ -21,12 +23,18 @@ use crate::{
 /// - `price`: The execution price
 /// - `quantity`: The quantity filled
 /// - `status`: The status of the order after the fill
-#[derive(Debug)]
+/// - `owner`: The owner of the counterparty order, if any
+/// - `maker_fee`: Fee charged to the counterparty (resting) order, negative for rebates
+/// - `taker_fee`: Fee charged to the incoming order for this fill
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FillReport {
     pub order_id: OrderId,
     pub price: Price,
     pub quantity: Quantity,
     pub status: OrderStatus,
+    pub owner: Option<OwnerId>,
+    pub maker_fee: i128,
+    pub taker_fee: i128,
 }
 
 #[derive(Debug)]
@@ -60,8 +68,9 @@ pub(crate) struct ExecutionReportParams {
 /// - `time_in_force`: Time-in-force policy applied
 /// - `post_only`: Whether the order was post-only
 /// - `fills`: Vector of individual fills
+/// - `fees`: Total taker fees paid by the order, the sum of `taker_fee` over `fills`
 /// - `log`: Optional journal log (if journaling is enabled)
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExecutionReport {
     pub order_id: OrderId,
     pub orig_qty: Quantity,
@@ -76,6 +85,7 @@ pub struct ExecutionReport {
     pub time_in_force: TimeInForce,
     pub post_only: bool,
     pub fills: Vec<FillReport>,
+    pub fees: i128,
     pub log: Option<JournalLog>,
 }
 
@@ -113,7 +123,29 @@ impl ExecutionReport {
             },
             post_only: params.post_only,
             fills: Vec::new(),
+            fees: 0,
             log: None,
         }
     }
 }
+
+/// A report describing the result of [`crate::OrderBook::uncross`].
+///
+/// In a call auction every executed order was resting on the book, so each
+/// order gets its own [`FillReport`], with its fee in `maker_fee`. Fills are
+/// listed in pairs: the buy order first, then the sell order it was matched with.
+///
+/// # Fields
+/// - `price`: The auction price, `None` if the book was not crossed
+/// - `quantity`: Total quantity executed at the auction price
+/// - `fills`: Fills of every executed order
+/// - `canceled`: Auction-only orders whose unfilled remainder was canceled
+/// - `log`: Optional journal log (if journaling is enabled)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UncrossReport {
+    pub price: Option<Price>,
+    pub quantity: Quantity,
+    pub fills: Vec<FillReport>,
+    pub canceled: Vec<OrderId>,
+    pub log: Option<JournalLog>,
+}
@@ -0,0 +1,249 @@
+//! Pre-trade risk checks and price protections applied by the order book
+//! before order validation.
+//!
+//! The limits are configured once through
+//! [`OrderBookBuilder::with_risk_limits`](crate::OrderBookBuilder::with_risk_limits)
+//! and [`OrderBookBuilder::with_price_band`](crate::OrderBookBuilder::with_price_band)
+//! and every submitted order is checked against them before it can reach the
+//! matching engine.
+
+use crate::order::{Price, Quantity};
+
+/// Configurable pre-trade risk limits (fat-finger protection).
+///
+/// Every limit is optional: a `None` value disables the corresponding check.
+///
+/// # Fields
+/// - `max_order_qty`: Maximum quantity allowed for a single order
+/// - `max_notional`: Maximum notional (`price × quantity`) allowed for a single order
+/// - `max_open_orders_per_owner`: Maximum number of resting orders per owner
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RiskLimits {
+    pub max_order_qty: Option<Quantity>,
+    pub max_notional: Option<u128>,
+    pub max_open_orders_per_owner: Option<usize>,
+}
+
+impl RiskLimits {
+    /// Sets the maximum quantity allowed for a single order.
+    pub fn with_max_order_qty(mut self, quantity: u64) -> Self {
+        self.max_order_qty = Some(Quantity(quantity));
+        self
+    }
+
+    /// Sets the maximum notional (`price × quantity`) allowed for a single order.
+    pub fn with_max_notional(mut self, notional: u128) -> Self {
+        self.max_notional = Some(notional);
+        self
+    }
+
+    /// Sets the maximum number of resting orders a single owner can have.
+    pub fn with_max_open_orders_per_owner(mut self, orders: usize) -> Self {
+        self.max_open_orders_per_owner = Some(orders);
+        self
+    }
+}
+
+/// The price a [`PriceBand`] is anchored to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferencePrice {
+    /// The price of the last trade executed by the book.
+    LastTrade,
+    /// The mid price between the best bid and the best ask.
+    Mid,
+    /// A price set externally with
+    /// [`OrderBook::set_reference_price`](crate::OrderBook::set_reference_price).
+    External,
+}
+
+/// What happens to a limit order priced outside the band.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceBandAction {
+    /// The order is rejected.
+    Reject,
+    /// The order price is capped to the band edge.
+    Cap,
+}
+
+/// Dynamic price band (collar) around a reference price.
+///
+/// Only the aggressive direction is checked: buy orders priced above the upper
+/// edge and sell orders priced below the lower edge. Market orders stop matching
+/// at the band edge and the unfilled remainder is not executed. When the
+/// reference price is not available (e.g. no trade yet) the band is not enforced.
+///
+/// # Fields
+/// - `reference`: The [`ReferencePrice`] the band is anchored to
+/// - `max_deviation_bps`: Maximum distance from the reference price, in basis points
+/// - `action`: The [`PriceBandAction`] applied to limit orders outside the band
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceBand {
+    pub reference: ReferencePrice,
+    pub max_deviation_bps: u64,
+    pub action: PriceBandAction,
+}
+
+impl PriceBand {
+    pub fn new(reference: ReferencePrice, max_deviation_bps: u64, action: PriceBandAction) -> Self {
+        Self { reference, max_deviation_bps, action }
+    }
+
+    /// Returns the `(lower, upper)` edges of the band around `reference`.
+    pub(crate) fn edges(&self, reference: Price) -> (Price, Price) {
+        deviation_edges(reference, self.max_deviation_bps)
+    }
+}
+
+/// The trading phase a [`VolatilityInterruption`] switches the book to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptionPhase {
+    /// Trading is halted, see [`TradingPhase::Halted`](crate::TradingPhase::Halted).
+    Halted,
+    /// Orders are collected for a volatility auction, see
+    /// [`TradingPhase::Auction`](crate::TradingPhase::Auction).
+    Auction,
+}
+
+/// What happens to the unfilled remainder of the limit order that triggered a
+/// volatility interruption. The remainder of a market order is never executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptionRemainder {
+    /// The remainder is canceled.
+    Cancel,
+    /// The remainder rests in the book at its limit price, to be collected by
+    /// the volatility auction. When the book is halted the remainder would
+    /// cross the book and is canceled instead.
+    Rest,
+}
+
+/// Volatility interruption (circuit breaker).
+///
+/// Matching stops as soon as the next trade would execute outside the static
+/// range around the external reference price (see
+/// [`OrderBook::set_reference_price`](crate::OrderBook::set_reference_price)) or
+/// outside the dynamic range around the last trade price. The trades executed so
+/// far are kept, the book switches to `phase` and the remainder of the aggressor
+/// order is handled according to `remainder`. A range whose reference price is
+/// not available is not enforced.
+///
+/// Fill-or-kill orders are checked for liquidity within the range before
+/// matching and rejected whole when it is not enough. The remainder of an
+/// interrupted fill-or-kill order is always canceled.
+///
+/// # Fields
+/// - `static_bps`: Maximum distance from the external reference price, in basis points
+/// - `dynamic_bps`: Maximum distance from the last trade price, in basis points
+/// - `phase`: The [`InterruptionPhase`] the book switches to
+/// - `remainder`: The [`InterruptionRemainder`] policy for the aggressor order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VolatilityInterruption {
+    pub static_bps: Option<u64>,
+    pub dynamic_bps: Option<u64>,
+    pub phase: InterruptionPhase,
+    pub remainder: InterruptionRemainder,
+}
+
+impl VolatilityInterruption {
+    pub fn new(phase: InterruptionPhase, remainder: InterruptionRemainder) -> Self {
+        Self { static_bps: None, dynamic_bps: None, phase, remainder }
+    }
+
+    /// Sets the static range around the external reference price.
+    pub fn with_static_bps(mut self, bps: u64) -> Self {
+        self.static_bps = Some(bps);
+        self
+    }
+
+    /// Sets the dynamic range around the last trade price.
+    pub fn with_dynamic_bps(mut self, bps: u64) -> Self {
+        self.dynamic_bps = Some(bps);
+        self
+    }
+
+    /// Returns the `(lower, upper)` prices trades can execute at, the
+    /// intersection of the enforced ranges, or `None` if no range is enforced.
+    pub(crate) fn range(
+        &self,
+        static_reference: Option<Price>,
+        dynamic_reference: Option<Price>,
+    ) -> Option<(Price, Price)> {
+        [(self.static_bps, static_reference), (self.dynamic_bps, dynamic_reference)]
+            .into_iter()
+            .filter_map(|(bps, reference)| Some(deviation_edges(reference?, bps?)))
+            .reduce(|(lower, upper), (l, u)| (lower.max(l), upper.min(u)))
+    }
+}
+
+/// Returns the prices `bps` basis points below and above `reference`.
+fn deviation_edges(reference: Price, bps: u64) -> (Price, Price) {
+    let reference = u128::from(reference.value());
+    let bps = u128::from(bps);
+    let lower = reference * 10_000u128.saturating_sub(bps) / 10_000;
+    let upper = reference.saturating_mul(10_000 + bps) / 10_000;
+    (Price(lower as u64), Price(u64::try_from(upper).unwrap_or(u64::MAX)))
+}
+
+/// Computes `price × quantity` without overflowing.
+pub(crate) fn notional(price: Price, quantity: Quantity) -> u128 {
+    u128::from(price.value()) * u128::from(quantity.value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_risk_limits_builder() {
+        let limits = RiskLimits::default()
+            .with_max_order_qty(100)
+            .with_max_notional(1_000_000)
+            .with_max_open_orders_per_owner(5);
+
+        assert_eq!(limits.max_order_qty, Some(Quantity(100)));
+        assert_eq!(limits.max_notional, Some(1_000_000));
+        assert_eq!(limits.max_open_orders_per_owner, Some(5));
+        assert_eq!(RiskLimits::default().max_order_qty, None);
+    }
+
+    #[test]
+    fn test_price_band_edges() {
+        let band = PriceBand::new(ReferencePrice::External, 500, PriceBandAction::Reject);
+        assert_eq!(band.edges(Price(1000)), (Price(950), Price(1050)));
+
+        // deviation larger than 100% floors the lower edge at zero
+        let band = PriceBand::new(ReferencePrice::External, 20_000, PriceBandAction::Reject);
+        assert_eq!(band.edges(Price(1000)), (Price(0), Price(3000)));
+
+        // the upper edge saturates instead of overflowing
+        assert_eq!(band.edges(Price(u64::MAX)).1, Price(u64::MAX));
+        let band = PriceBand::new(ReferencePrice::External, u64::MAX, PriceBandAction::Reject);
+        assert_eq!(band.edges(Price(u64::MAX)), (Price(0), Price(u64::MAX)));
+        assert_eq!(band.edges(Price(1)), (Price(0), Price(u64::MAX / 10_000 + 1)));
+    }
+
+    #[test]
+    fn test_volatility_interruption_range() {
+        let vi =
+            VolatilityInterruption::new(InterruptionPhase::Halted, InterruptionRemainder::Cancel);
+        assert_eq!(vi.range(Some(Price(1000)), Some(Price(1000))), None);
+
+        let vi = vi.with_static_bps(1000).with_dynamic_bps(200);
+        assert_eq!(vi.range(Some(Price(1000)), None), Some((Price(900), Price(1100))));
+        assert_eq!(vi.range(None, Some(Price(1050))), Some((Price(1029), Price(1071))));
+        // the tightest edges of both ranges
+        assert_eq!(
+            vi.range(Some(Price(1000)), Some(Price(1090))),
+            Some((Price(1068), Price(1100)))
+        );
+        assert_eq!(vi.range(None, None), None);
+    }
+
+    #[test]
+    fn test_notional_does_not_overflow() {
+        assert_eq!(notional(Price(1000), Quantity(5)), 5000);
+        assert_eq!(
+            notional(Price(u64::MAX), Quantity(u64::MAX)),
+            u128::from(u64::MAX) * u128::from(u64::MAX)
+        );
+    }
+}
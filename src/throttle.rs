@@ -0,0 +1,163 @@
+//! Per-owner message throttling.
+//!
+//! When enabled with
+//! [`OrderBookBuilder::with_throttle`](crate::OrderBookBuilder::with_throttle),
+//! every message (market, limit, modify and cancel) submitted by an owner is
+//! counted in a sliding time window and new orders are checked against the
+//! owner's order-to-trade ratio. Time is read from the engine clock
+//! ([`OrderBook::now`](crate::OrderBook::now)), so that replaying a journal
+//! throttles exactly like the original run.
+//!
+//! Only accepted messages are counted and orders without an owner are never
+//! throttled.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{make_error, ErrorType, Result},
+    order::OwnerId,
+};
+
+/// Configurable throttling limits.
+///
+/// Every limit is optional: a `None` value disables the corresponding check.
+///
+/// # Fields
+/// - `max_messages`: Maximum number of messages per owner within `window_ms`
+/// - `window_ms`: Length of the sliding window, in milliseconds
+/// - `max_order_to_trade_ratio`: Maximum number of orders per trade
+/// - `min_orders`: Number of orders an owner can submit before the ratio is enforced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThrottleLimits {
+    pub max_messages: Option<usize>,
+    pub window_ms: i64,
+    pub max_order_to_trade_ratio: Option<u64>,
+    pub min_orders: u64,
+}
+
+impl ThrottleLimits {
+    /// Limits every owner to `messages` messages per `window_ms` milliseconds.
+    pub fn with_max_messages(mut self, messages: usize, window_ms: i64) -> Self {
+        self.max_messages = Some(messages);
+        self.window_ms = window_ms;
+        self
+    }
+
+    /// Rejects new orders of an owner that has submitted more than `ratio`
+    /// orders per trade, once it has submitted at least `min_orders` orders.
+    pub fn with_max_order_to_trade_ratio(mut self, ratio: u64, min_orders: u64) -> Self {
+        self.max_order_to_trade_ratio = Some(ratio);
+        self.min_orders = min_orders;
+        self
+    }
+}
+
+/// Throttling counters of an owner.
+///
+/// # Fields
+/// - `messages`: Timestamps of the messages within the current window
+/// - `orders`: Number of orders (market, limit and modify) submitted
+/// - `trades`: Number of fills the owner took part in, as maker or taker
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThrottleActivity {
+    pub messages: VecDeque<i64>,
+    pub orders: u64,
+    pub trades: u64,
+}
+
+/// Throttling limits together with the activity of every owner.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Throttle {
+    pub(crate) limits: ThrottleLimits,
+    pub(crate) activity: HashMap<OwnerId, ThrottleActivity>,
+}
+
+impl Throttle {
+    pub(crate) fn new(limits: ThrottleLimits) -> Self {
+        Self { limits, activity: HashMap::new() }
+    }
+
+    /// Checks whether `owner` can send a message at `now`. `is_order` is
+    /// `false` for cancellations, which are never subject to the ratio.
+    pub(crate) fn check(&self, owner: OwnerId, now: i64, is_order: bool) -> Result<()> {
+        let Some(activity) = self.activity.get(&owner) else { return Ok(()) };
+        if let Some(max) = self.limits.max_messages {
+            let start = now - self.limits.window_ms;
+            if activity.messages.iter().filter(|ts| **ts > start).count() >= max {
+                return Err(make_error(ErrorType::ThrottleMessageRate));
+            }
+        }
+        if let Some(ratio) = self.limits.max_order_to_trade_ratio {
+            if is_order
+                && activity.orders >= self.limits.min_orders
+                && activity.orders >= ratio.saturating_mul(activity.trades.max(1))
+            {
+                return Err(make_error(ErrorType::ThrottleOrderToTradeRatio));
+            }
+        }
+        Ok(())
+    }
+
+    /// Records an accepted message of `owner` at `now`.
+    pub(crate) fn record(&mut self, owner: OwnerId, now: i64, is_order: bool) {
+        let start = now - self.limits.window_ms;
+        let activity = self.activity.entry(owner).or_default();
+        while activity.messages.front().is_some_and(|ts| *ts <= start) {
+            activity.messages.pop_front();
+        }
+        if self.limits.max_messages.is_some() {
+            activity.messages.push_back(now);
+        }
+        if is_order {
+            activity.orders += 1;
+        }
+    }
+
+    /// Records a fill `owner` took part in.
+    pub(crate) fn record_trade(&mut self, owner: OwnerId) {
+        self.activity.entry(owner).or_default().trades += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_window() {
+        let owner = OwnerId(1);
+        let mut throttle = Throttle::new(ThrottleLimits::default().with_max_messages(2, 1000));
+
+        throttle.record(owner, 0, true);
+        throttle.record(owner, 500, false);
+        let err = throttle.check(owner, 999, false).unwrap_err();
+        assert_eq!(err.code, make_error(ErrorType::ThrottleMessageRate).code);
+        // the first message left the window
+        assert!(throttle.check(owner, 1000, true).is_ok());
+        throttle.record(owner, 1000, true);
+        assert_eq!(throttle.activity[&owner].messages, VecDeque::from([500, 1000]));
+        assert!(throttle.check(OwnerId(2), 1000, true).is_ok());
+    }
+
+    #[test]
+    fn test_order_to_trade_ratio() {
+        let owner = OwnerId(1);
+        let mut throttle =
+            Throttle::new(ThrottleLimits::default().with_max_order_to_trade_ratio(2, 3));
+
+        for _ in 0..3 {
+            assert!(throttle.check(owner, 0, true).is_ok());
+            throttle.record(owner, 0, true);
+        }
+        let err = throttle.check(owner, 0, true).unwrap_err();
+        assert_eq!(err.code, make_error(ErrorType::ThrottleOrderToTradeRatio).code);
+        // cancellations are always allowed
+        assert!(throttle.check(owner, 0, false).is_ok());
+
+        throttle.record_trade(owner);
+        throttle.record_trade(owner);
+        assert!(throttle.check(owner, 0, true).is_ok());
+    }
+}
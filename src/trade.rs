@@ -0,0 +1,54 @@
+//! Trades executed by the order book.
+//!
+//! Every execution between two orders is recorded as a [`Trade`] with a
+//! sequential [`TradeId`], kept in a bounded buffer of recent trades (see
+//! [`OrderBookBuilder::with_trade_history`](crate::OrderBookBuilder::with_trade_history))
+//! and published to the listeners as an
+//! [`OrderBookEvent::Trade`](crate::OrderBookEvent::Trade) event.
+
+use std::ops::AddAssign;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    order::{OrderId, Price, Quantity},
+    Side,
+};
+
+/// Number of trades kept by default in the recent trades buffer.
+pub(crate) const DEFAULT_TRADE_HISTORY: usize = 1_000;
+
+/// Sequential identifier of a trade.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, Hash, PartialOrd, Ord,
+)]
+pub struct TradeId(pub u64);
+impl AddAssign<u64> for TradeId {
+    fn add_assign(&mut self, rhs: u64) {
+        self.0 += rhs;
+    }
+}
+
+/// An execution between a resting (maker) order and an incoming (taker) order.
+///
+/// Auction trades have no aggressor: the order submitted first is reported as
+/// the maker.
+///
+/// # Fields
+/// - `id`: Sequential trade ID
+/// - `maker_order_id`: ID of the resting order
+/// - `taker_order_id`: ID of the incoming order
+/// - `aggressor`: Side of the incoming order, `None` for auction trades
+/// - `price`: The execution price
+/// - `quantity`: The executed quantity
+/// - `ts`: Timestamp of the execution, from the clock of the book
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Trade {
+    pub id: TradeId,
+    pub maker_order_id: OrderId,
+    pub taker_order_id: OrderId,
+    pub aggressor: Option<Side>,
+    pub price: Price,
+    pub quantity: Quantity,
+    pub ts: i64,
+}